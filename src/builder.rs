@@ -0,0 +1,139 @@
+//! Declarative construction of a [`Graph`].
+use crate::errors::GraphError;
+use crate::weight::Weight;
+use crate::{Graph, GrowthPolicy, Node, NodeId, SelfLoops, WeightValidator};
+
+/// Collects the configuration, nodes and edges of a [`Graph`] and builds it
+/// in one step.
+/// By default the graph is directed, not a multigraph, allows self-loops,
+/// starts with room for the initial nodes and grows with
+/// [`GrowthPolicy::Doubling`].
+///
+/// # Example
+/// ```
+/// use graphs::builder::GraphBuilder;
+/// use graphs::{Node, SelfLoops};
+///
+/// let graph = GraphBuilder::<&str>::new()
+///     .undirected(true)
+///     .self_loops(SelfLoops::Reject)
+///     .weight_validator(|w| *w > 0.0)
+///     .node(Node::with_label(1, "a"))
+///     .node(Node::with_label(2, "b"))
+///     .edge(1, 2, 4.0)
+///     .build()
+///     .unwrap();
+/// assert!(graph.is_edge(2, 1));
+/// ```
+pub struct GraphBuilder<T, W = f32, E = (), Id = u32> {
+    capacity: Option<usize>,
+    undirected: bool,
+    multigraph: bool,
+    self_loops: SelfLoops,
+    growth: GrowthPolicy,
+    weight_validator: Option<WeightValidator<W>>,
+    nodes: Vec<Node<T, W, E, Id>>,
+    edges: Vec<(Id, Id, W, E)>,
+}
+
+impl<T, W: Weight, E, Id: NodeId> GraphBuilder<T, W, E, Id> {
+    /// Creates a builder with the default configuration and no nodes.
+    pub fn new() -> Self {
+        Self {
+            capacity: None,
+            undirected: false,
+            multigraph: false,
+            self_loops: SelfLoops::default(),
+            growth: GrowthPolicy::Doubling,
+            weight_validator: None,
+            nodes: Vec::new(),
+            edges: Vec::new(),
+        }
+    }
+    /// Sets the initial capacity of the graph.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = Some(capacity);
+        self
+    }
+    /// Sets whether the graph is undirected.
+    pub fn undirected(mut self, undirected: bool) -> Self {
+        self.undirected = undirected;
+        self
+    }
+    /// Sets whether the graph keeps parallel edges; see
+    /// [`Graph::new_multigraph`].
+    pub fn multigraph(mut self, multigraph: bool) -> Self {
+        self.multigraph = multigraph;
+        self
+    }
+    /// Sets the self-loop policy; see [`Graph::with_self_loops`].
+    pub fn self_loops(mut self, policy: SelfLoops) -> Self {
+        self.self_loops = policy;
+        self
+    }
+    /// Sets the growth policy; see [`Graph::with_growth_policy`].
+    pub fn growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = policy;
+        self
+    }
+    /// Sets a check for edge weights; see [`Graph::with_weight_validator`].
+    pub fn weight_validator(
+        mut self,
+        validator: impl Fn(&W) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.weight_validator = Some(Box::new(validator));
+        self
+    }
+    /// Adds a node to insert when the graph is built.
+    pub fn node(mut self, node: Node<T, W, E, Id>) -> Self {
+        self.nodes.push(node);
+        self
+    }
+    /// Adds several nodes to insert when the graph is built.
+    pub fn nodes(mut self, nodes: impl IntoIterator<Item = Node<T, W, E, Id>>) -> Self {
+        self.nodes.extend(nodes);
+        self
+    }
+    /// Adds an edge to insert when the graph is built, after all nodes.
+    pub fn edge(self, from: Id, to: Id, weight: W) -> Self
+    where
+        E: Default,
+    {
+        self.edge_with_data(from, to, weight, E::default())
+    }
+    /// Adds an edge carrying `data` to insert when the graph is built.
+    pub fn edge_with_data(mut self, from: Id, to: Id, weight: W, data: E) -> Self {
+        self.edges.push((from, to, weight, data));
+        self
+    }
+    /// Creates the graph and inserts the nodes, then the edges, in the order
+    /// they were added. Returns the first error, such as a duplicate node, an
+    /// edge to a missing node, an invalid weight or a rejected self-loop.
+    pub fn build(self) -> Result<Graph<T, W, E, Id>, GraphError<Id>>
+    where
+        E: Clone,
+    {
+        let capacity = self.capacity.unwrap_or(self.nodes.len());
+        let mut graph = if self.multigraph {
+            Graph::new_multigraph(capacity, self.undirected)
+        } else {
+            Graph::new(capacity, self.undirected)
+        }
+        .with_self_loops(self.self_loops)
+        .with_growth_policy(self.growth);
+        graph.weight_validator = self.weight_validator;
+        for node in self.nodes {
+            graph.insert_node(node)?;
+        }
+        for (from, to, weight, data) in self.edges {
+            graph.insert_edge_with_data(from, to, weight, data)?;
+        }
+        Ok(graph)
+    }
+}
+
+impl<T, W: Weight, E, Id: NodeId> Default for GraphBuilder<T, W, E, Id> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,5 +1,114 @@
-#[derive(Debug)]
-pub enum GraphError {
-    MissingNode,
-    CapacityExceeded,
+use std::error::Error;
+use std::fmt;
+
+use crate::schema::PropertyType;
+
+/// The error type for fallible [`Graph`](crate::Graph) operations.
+/// `Id` is the node id type of the graph, `u32` by default.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GraphError<Id = u32> {
+    /// No node with this index number exists in the graph.
+    MissingNode { idx: Id },
+    /// The node a [`NodeHandle`](crate::NodeHandle) refers to has been
+    /// removed from the graph.
+    StaleHandle { idx: Id },
+    /// The source node of an edge doesn't exist in the graph.
+    MissingSourceNode { idx: Id },
+    /// The target node of an edge doesn't exist in the graph.
+    MissingTargetNode { idx: Id },
+    /// A node with this index number already exists in the graph.
+    DuplicateNode { idx: Id },
+    /// No edge exists between the two nodes.
+    MissingEdge { from: Id, to: Id },
+    /// The weight given for the edge between the two nodes is not usable,
+    /// for example `NaN`.
+    InvalidWeight { from: Id, to: Id },
+    /// A self-loop on this node was rejected by the graph's
+    /// [`SelfLoops`](crate::SelfLoops) policy.
+    SelfLoop { idx: Id },
+    /// The operation requires an acyclic graph, but a cycle runs through
+    /// this node.
+    CycleDetected { idx: Id },
+    /// Every id of the node id type is already in use, so no new id can be
+    /// allocated.
+    IdsExhausted,
+    /// The graph already holds as many nodes as its capacity allows.
+    CapacityExceeded { capacity: usize },
+    /// The node lacks a property required by the graph's
+    /// [`Schema`](crate::schema::Schema), or has it with the wrong type.
+    InvalidNodeProperty {
+        idx: Id,
+        key: String,
+        expected: PropertyType,
+    },
+    /// The edge between the two nodes lacks a property required by the
+    /// graph's schema, or has it with the wrong type.
+    InvalidEdgeProperty {
+        from: Id,
+        to: Id,
+        key: String,
+        expected: PropertyType,
+    },
+    /// The schema doesn't allow edges with this label between the labels of
+    /// the two nodes.
+    InvalidEdgeEndpoints { from: Id, to: Id, label: String },
 }
+
+impl<Id: fmt::Debug> fmt::Display for GraphError<Id> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::MissingNode { idx } => write!(f, "node {idx:?} does not exist"),
+            GraphError::StaleHandle { idx } => {
+                write!(f, "handle to node {idx:?} is stale")
+            }
+            GraphError::MissingSourceNode { idx } => {
+                write!(f, "source node {idx:?} does not exist")
+            }
+            GraphError::MissingTargetNode { idx } => {
+                write!(f, "target node {idx:?} does not exist")
+            }
+            GraphError::DuplicateNode { idx } => write!(f, "node {idx:?} already exists"),
+            GraphError::MissingEdge { from, to } => {
+                write!(f, "no edge exists from node {from:?} to node {to:?}")
+            }
+            GraphError::InvalidWeight { from, to } => {
+                write!(
+                    f,
+                    "invalid weight for edge from node {from:?} to node {to:?}"
+                )
+            }
+            GraphError::SelfLoop { idx } => {
+                write!(f, "self-loop on node {idx:?} is not allowed")
+            }
+            GraphError::CycleDetected { idx } => {
+                write!(f, "cycle detected through node {idx:?}")
+            }
+            GraphError::IdsExhausted => write!(f, "no free node ids left"),
+            GraphError::CapacityExceeded { capacity } => {
+                write!(f, "graph capacity of {capacity} nodes exceeded")
+            }
+            GraphError::InvalidNodeProperty { idx, key, expected } => {
+                write!(f, "node {idx:?} needs a {expected} property {key:?}")
+            }
+            GraphError::InvalidEdgeProperty {
+                from,
+                to,
+                key,
+                expected,
+            } => {
+                write!(
+                    f,
+                    "edge from node {from:?} to node {to:?} needs a {expected} property {key:?}"
+                )
+            }
+            GraphError::InvalidEdgeEndpoints { from, to, label } => {
+                write!(
+                    f,
+                    "{label:?} edges can't go from node {from:?} to node {to:?}"
+                )
+            }
+        }
+    }
+}
+
+impl<Id: fmt::Debug> Error for GraphError<Id> {}
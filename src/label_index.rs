@@ -0,0 +1,134 @@
+//! An optional index from node labels to node ids.
+use std::collections::hash_map::RandomState;
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasher, Hash};
+
+use crate::errors::GraphError;
+use crate::weight::Weight;
+use crate::{Graph, NodeId};
+
+/// Node ids grouped by the hash of their label.
+/// The hash function is stored when the index is enabled, so the graph can
+/// keep the index up to date without requiring `T: Hash` everywhere. Lookups
+/// compare the labels themselves, so hash collisions are harmless.
+pub(crate) struct LabelIndex<T, Id> {
+    state: RandomState,
+    hash: fn(&RandomState, &T) -> u64,
+    ids: HashMap<u64, HashSet<Id>>,
+}
+
+impl<T, Id: NodeId> LabelIndex<T, Id> {
+    fn new() -> Self
+    where
+        T: Hash,
+    {
+        Self {
+            state: RandomState::new(),
+            hash: |state, label| state.hash_one(label),
+            ids: HashMap::new(),
+        }
+    }
+    pub(crate) fn insert(&mut self, label: &T, idx: Id) {
+        let hash = (self.hash)(&self.state, label);
+        self.ids.entry(hash).or_default().insert(idx);
+    }
+    pub(crate) fn remove(&mut self, label: &T, idx: &Id) {
+        let hash = (self.hash)(&self.state, label);
+        if let Some(ids) = self.ids.get_mut(&hash) {
+            ids.remove(idx);
+            if ids.is_empty() {
+                self.ids.remove(&hash);
+            }
+        }
+    }
+    fn candidates(&self, label: &T) -> impl Iterator<Item = &Id> {
+        let hash = (self.hash)(&self.state, label);
+        self.ids.get(&hash).into_iter().flatten()
+    }
+}
+
+impl<T, W: Weight, E, Id: NodeId> Graph<T, W, E, Id> {
+    /// Enables the label index, indexing the nodes already in the graph, so
+    /// [`Graph::find_by_label`] doesn't have to scan every node.
+    /// The index follows node insertion, merging and removal, and
+    /// [`Graph::set_label`]. Labels changed through `nodes`,
+    /// [`Graph::nodes_mut`] or [`Graph::get_node_mut`] bypass it.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<&str> = Graph::new(5, false).with_label_index();
+    /// graph.insert_node(Node::with_label(1, "alice")).unwrap();
+    /// graph.insert_node(Node::with_label(2, "bob")).unwrap();
+    /// assert_eq!(graph.find_by_label(&"bob").collect::<Vec<_>>(), vec![&2]);
+    ///
+    /// graph.set_label(2, "carol").unwrap();
+    /// assert_eq!(graph.find_by_label(&"bob").count(), 0);
+    /// ```
+    pub fn with_label_index(mut self) -> Self
+    where
+        T: Hash + Eq,
+    {
+        let mut labels = LabelIndex::new();
+        for node in &self.nodes {
+            if let Some(label) = &node.label {
+                labels.insert(label, node.idx.clone());
+            }
+        }
+        self.labels = Some(labels);
+        self
+    }
+    /// Returns `true` if the label index is enabled.
+    pub fn has_label_index(&self) -> bool {
+        self.labels.is_some()
+    }
+    /// Returns an iterator over the ids of the nodes labelled `label`.
+    /// Uses the label index if it is enabled and scans the nodes otherwise.
+    /// With the index enabled the ids come in no particular order.
+    pub fn find_by_label<'a>(&'a self, label: &'a T) -> impl Iterator<Item = &'a Id>
+    where
+        T: Hash + Eq,
+    {
+        let (indexed, scanned) = match &self.labels {
+            Some(labels) => (Some(labels.candidates(label)), None),
+            None => (None, Some(self.nodes.iter().map(|node| &node.idx))),
+        };
+        indexed
+            .into_iter()
+            .flatten()
+            .chain(scanned.into_iter().flatten())
+            .filter(move |idx| {
+                self.node(idx)
+                    .is_some_and(|node| node.label.as_ref() == Some(label))
+            })
+    }
+    /// Sets the label of a node and returns the old one, keeping the label
+    /// index up to date. Returns `GraphError::MissingNode` if the node
+    /// doesn't exist. If a [`Schema`](crate::schema::Schema) is attached, the
+    /// node and its edges must satisfy it with the new label, otherwise the
+    /// label is left unchanged. Observers are notified through
+    /// [`GraphObserver::on_node_updated`](crate::observer::GraphObserver::on_node_updated).
+    pub fn set_label(&mut self, idx: Id, label: T) -> Result<Option<T>, GraphError<Id>> {
+        let Some(&pos) = self.index.get(&idx) else {
+            return Err(GraphError::MissingNode { idx });
+        };
+        let old = self.nodes[pos].label.replace(label);
+        if let Err(err) = self.check_schema(&self.nodes[pos], &HashMap::new()) {
+            self.nodes[pos].label = old;
+            return Err(err);
+        }
+        if let Some(labels) = self.labels.as_mut() {
+            if let Some(old) = &old {
+                labels.remove(old, &idx);
+            }
+            if let Some(label) = &self.nodes[pos].label {
+                labels.insert(label, idx);
+            }
+        }
+        for observer in &mut self.observers {
+            observer.on_node_updated(&self.nodes[pos]);
+        }
+        Ok(old)
+    }
+}
@@ -1,38 +1,229 @@
 //! A graph algorithms library for learning purposes.
 use std::collections::HashMap;
+use std::fmt::{self, Debug};
+use std::hash::Hash;
+use std::ops::{Deref, DerefMut};
+pub mod builder;
 pub mod errors;
+mod label_index;
+pub mod observer;
+pub mod property;
+pub mod schema;
+pub mod traversal;
+pub mod validation;
+pub mod weight;
 
 use errors::GraphError;
+use label_index::LabelIndex;
+use observer::GraphObserver;
+use schema::SchemaCheck;
+use weight::Weight;
+
+/// A type that can identify the nodes of a [`Graph`].
+/// It is implemented for every `Hash + Eq + Clone + Debug` type, so node ids
+/// can be integers, strings, UUIDs and so on. `Debug` lets errors name the
+/// node. Graphs use `u32` ids by default.
+pub trait NodeId: Hash + Eq + Clone + Debug {}
+
+impl<Id: Hash + Eq + Clone + Debug> NodeId for Id {}
+
+/// A node id type that [`Graph::add_node`] can allocate automatically.
+/// Ids are handed out in sequence starting from [`SequentialId::initial`].
+/// It is implemented for the primitive integer types.
+pub trait SequentialId: NodeId {
+    /// Returns the first id in the sequence.
+    fn initial() -> Self;
+    /// Returns the id after this one, or `None` if there is none.
+    fn successor(&self) -> Option<Self>;
+}
+
+macro_rules! impl_sequential_id {
+    ($($t:ty),*) => {
+        $(
+            impl SequentialId for $t {
+                fn initial() -> Self {
+                    0
+                }
+                fn successor(&self) -> Option<Self> {
+                    self.checked_add(1)
+                }
+            }
+        )*
+    };
+}
+
+impl_sequential_id!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// A generational reference to a node in a [`Graph`].
+/// Besides the node id, a handle records the generation the node was given
+/// when it was inserted; every inserted node gets a new one. Once the node is
+/// removed, the handle goes stale and stays stale, even if a new node reuses
+/// the id.
+/// Get one from [`Graph::handle`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle<Id = u32> {
+    idx: Id,
+    generation: u64,
+}
+
+impl<Id> NodeHandle<Id> {
+    /// Returns the id of the node the handle refers to.
+    pub fn id(&self) -> &Id {
+        &self.idx
+    }
+    /// Returns the generation of the node the handle refers to.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// How a [`Graph`] treats an edge from a node to itself.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SelfLoops {
+    /// Self-loops are inserted like any other edge.
+    #[default]
+    Allow,
+    /// Inserting a self-loop fails with `GraphError::SelfLoop`.
+    Reject,
+    /// Self-loops are silently dropped.
+    Ignore,
+}
+
+/// How a [`Graph`] reacts when a node is inserted while the graph is at its
+/// `capacity`.
+pub enum GrowthPolicy {
+    /// Inserting fails with `GraphError::CapacityExceeded`.
+    Fixed,
+    /// The capacity doubles, and room for that many nodes is allocated up
+    /// front.
+    Doubling,
+    /// The graph grows as needed, with `capacity` following the allocated
+    /// room. `on_soft_limit` is called with the node count whenever an insert
+    /// takes the graph past `soft_limit` nodes. It must be `Send + Sync`
+    /// so the graph can be shared across threads.
+    Unbounded {
+        soft_limit: usize,
+        on_soft_limit: Box<dyn FnMut(usize) + Send + Sync>,
+    },
+}
+
+impl fmt::Debug for GrowthPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GrowthPolicy::Fixed => write!(f, "Fixed"),
+            GrowthPolicy::Doubling => write!(f, "Doubling"),
+            GrowthPolicy::Unbounded { soft_limit, .. } => f
+                .debug_struct("Unbounded")
+                .field("soft_limit", soft_limit)
+                .finish_non_exhaustive(),
+        }
+    }
+}
+
+/// A stable identifier of an edge, assigned by the [`Graph`] when the edge is
+/// inserted. It tells parallel edges between the same two nodes apart.
+/// In an undirected graph, an edge and its mirror share the same id.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId(pub u64);
 
 /// A weighted edge in a graph between two nodes.
-pub struct Edge {
-    from_node: u32,
-    to_node: u32,
-    weight: f32,
+/// The weight type `W` defaults to `f32`; see [`Weight`] for other types.
+/// Each edge also carries a payload of type `E`, which defaults to `()`, for
+/// domain data such as a relationship type or a timestamp.
+/// Its endpoints are node ids of type `Id`, which defaults to `u32`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Edge<W = f32, E = (), Id = u32> {
+    id: EdgeId,
+    from_node: Id,
+    to_node: Id,
+    weight: W,
+    data: E,
 }
 
-impl Edge {
+impl<W, E, Id> Edge<W, E, Id> {
     /// Creates a new edge with weight betweeen two nodes.
-    pub fn new(from_node: u32, to_node: u32, weight: f32) -> Self {
+    /// The payload is set to its default value.
+    pub fn new(from_node: Id, to_node: Id, weight: W) -> Self
+    where
+        E: Default,
+    {
+        Self::with_data(from_node, to_node, weight, E::default())
+    }
+    /// Creates a new edge with weight and a payload between two nodes.
+    pub fn with_data(from_node: Id, to_node: Id, weight: W, data: E) -> Self {
         Self {
+            id: EdgeId::default(),
             from_node,
             to_node,
             weight,
+            data,
+        }
+    }
+    /// Returns the id of the edge.
+    /// Edges that were not inserted through a [`Graph`] have the default id.
+    pub fn id(&self) -> EdgeId {
+        self.id
+    }
+    /// Returns the id of the node the edge starts from.
+    pub fn from_node(&self) -> &Id {
+        &self.from_node
+    }
+    /// Returns the id of the node the edge points to.
+    pub fn to_node(&self) -> &Id {
+        &self.to_node
+    }
+    /// Returns a reference to the weight of the edge.
+    pub fn weight(&self) -> &W {
+        &self.weight
+    }
+    /// Returns a mutable reference to the weight of the edge.
+    /// Unlike [`Graph::update_weight`], this doesn't check that the new weight
+    /// is valid.
+    pub fn weight_mut(&mut self) -> &mut W {
+        &mut self.weight
+    }
+    /// Returns the same edge pointing the other way, keeping its id, weight
+    /// and payload. In an undirected graph this is the stored mirror.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::Edge;
+    ///
+    /// let edge: Edge = Edge::new(1, 2, 0.5).reversed();
+    /// assert_eq!((*edge.from_node(), *edge.to_node()), (2, 1));
+    /// assert_eq!(*edge.weight(), 0.5);
+    /// ```
+    pub fn reversed(self) -> Self {
+        Self {
+            from_node: self.to_node,
+            to_node: self.from_node,
+            ..self
         }
     }
+    /// Returns a reference to the payload of the edge.
+    pub fn data(&self) -> &E {
+        &self.data
+    }
+    /// Returns a mutable reference to the payload of the edge.
+    pub fn data_mut(&mut self) -> &mut E {
+        &mut self.data
+    }
 }
 
-/// A node or vertex in a graph with an index number.
+/// A node or vertex in a graph with an index number of type `Id`, which
+/// defaults to `u32`.
 /// Its edges are represented as an adjacency list, implemented as a hash map.
 /// Each key of the hash map represents the index of number of the node it connects
-/// to. The value contains the [`Edge`] object.
-pub struct Node<T> {
-    pub idx: u32,
-    pub edges: HashMap<u32, Edge>,
+/// to. The value contains the [`Edge`] objects to that node, oldest first.
+/// Outside of a multigraph there is at most one edge per neighbor.
+#[derive(Debug)]
+pub struct Node<T, W = f32, E = (), Id = u32> {
+    pub idx: Id,
+    pub edges: HashMap<Id, Vec<Edge<W, E, Id>>>,
     pub label: Option<T>,
 }
 
-impl<T> Node<T> {
+impl<T, W, E, Id: NodeId> Node<T, W, E, Id> {
     /// Creates a new node without label.
     /// # Example
     /// ```
@@ -42,7 +233,7 @@ impl<T> Node<T> {
     /// assert_eq!(node.idx, 10);
     /// ```
     ///
-    pub fn new(idx: u32) -> Self {
+    pub fn new(idx: Id) -> Self {
         Self {
             idx,
             edges: HashMap::new(),
@@ -64,59 +255,263 @@ impl<T> Node<T> {
     /// }
     ///
     /// let label = Cart { amount: 510.50, name: String::from("Alex Jones")};
-    /// let labelled_node = Node::with_label(0, label);
+    /// let labelled_node: Node<Cart> = Node::with_label(0, label);
     /// assert_eq!(labelled_node.idx, 0);
     /// ```
-    pub fn with_label(idx: u32, label: T) -> Self {
+    pub fn with_label(idx: Id, label: T) -> Self {
         Self {
             idx,
             edges: HashMap::new(),
             label: Some(label),
         }
     }
-    /// Gets the number of edges.
+    /// Gets the number of edges, counting parallel edges separately.
     pub fn number_of_edges(&self) -> usize {
-        self.edges.len()
+        self.edges.values().map(Vec::len).sum()
     }
     /// Returns a reference to the edge object that connects this node with the
-    /// `neighbor` node.
-    pub fn get_edge(&self, neighbor: u32) -> Option<&Edge> {
-        self.edges.get(&neighbor)
+    /// `neighbor` node. If there are parallel edges, returns the oldest one.
+    pub fn get_edge(&self, neighbor: Id) -> Option<&Edge<W, E, Id>> {
+        self.edges.get(&neighbor).and_then(|edges| edges.first())
     }
     /// Connects an edge between this node and the `neighbor` node.
     /// If the source node already has an edge to a neighbor node,
     /// it updates the edge data and returns the old edge value.
     /// Otherwise returns `None`.
-    pub fn add_edge(&mut self, neighbor: u32, weight: f32) -> Option<Edge> {
-        let new_edge = Edge::new(self.idx, neighbor, weight);
-        eprintln!("{:?}", new_edge.from_node);
-        eprintln!("{:?}", new_edge.to_node);
-        eprintln!("{:?}", new_edge.weight);
-        self.edges.insert(neighbor, new_edge)
+    pub fn add_edge(&mut self, neighbor: Id, weight: W) -> Option<Edge<W, E, Id>>
+    where
+        E: Default,
+    {
+        self.add_edge_with_data(neighbor, weight, E::default())
+    }
+    /// Connects an edge carrying `data` between this node and the `neighbor`
+    /// node. Behaves like [`Node::add_edge`] otherwise.
+    pub fn add_edge_with_data(
+        &mut self,
+        neighbor: Id,
+        weight: W,
+        data: E,
+    ) -> Option<Edge<W, E, Id>> {
+        let new_edge = Edge::with_data(self.idx.clone(), neighbor, weight, data);
+        self.put_edge(new_edge, false)
     }
     /// Removes an edge between two nodes.
     /// Returns the index of the neighbor node and the edge value if the edge existed between
-    /// the two nodes.
-    pub fn remove_edge(&mut self, neighbor: u32) -> Option<(u32, Edge)> {
-        self.edges.remove_entry(&neighbor)
+    /// the two nodes. If there are parallel edges to the neighbor, only the
+    /// oldest one is removed, like [`Graph::remove_edge`] does.
+    pub fn remove_edge(&mut self, neighbor: Id) -> Option<Removed<W, E, Id>> {
+        let id = self.edges.get(&neighbor)?.first()?.id;
+        self.take_edge(&neighbor, id).map(|edge| (neighbor, edge))
+    }
+    /// Stores `edge` in the adjacency list.
+    /// With `parallel`, the edge is added after any existing edges to the same
+    /// neighbor. Otherwise it replaces the existing edge, keeping its id, and
+    /// the replaced edge is returned.
+    fn put_edge(&mut self, mut edge: Edge<W, E, Id>, parallel: bool) -> Option<Edge<W, E, Id>> {
+        let edges = self.edges.entry(edge.to_node.clone()).or_default();
+        match edges.first_mut() {
+            Some(old) if !parallel => {
+                edge.id = old.id;
+                Some(std::mem::replace(old, edge))
+            }
+            _ => {
+                edges.push(edge);
+                None
+            }
+        }
+    }
+    /// Returns a mutable reference to the edge with the given id to `neighbor`.
+    fn edge_mut(&mut self, neighbor: &Id, id: EdgeId) -> Option<&mut Edge<W, E, Id>> {
+        self.edges
+            .get_mut(neighbor)?
+            .iter_mut()
+            .find(|edge| edge.id == id)
+    }
+    /// Removes the edge with the given id to `neighbor`.
+    fn take_edge(&mut self, neighbor: &Id, id: EdgeId) -> Option<Edge<W, E, Id>> {
+        let edges = self.edges.get_mut(neighbor)?;
+        let pos = edges.iter().position(|edge| edge.id == id)?;
+        let edge = edges.remove(pos);
+        if edges.is_empty() {
+            self.edges.remove(neighbor);
+        }
+        Some(edge)
     }
 }
 
-pub struct Graph<T> {
+/// A graph made up of [`Node`]s.
+/// Nodes are stored in insertion order in `nodes`, alongside an index from each
+/// node's index number to its position in `nodes`, so node lookups take
+/// constant time. The graph also keeps track of the incoming edges of every
+/// node. Pushing to or reordering `nodes`, or editing `Node::edges`, directly
+/// bypasses these indexes; use the `Graph` methods instead.
+/// Edge weights are of type `W`, which defaults to `f32`, and edge payloads
+/// are of type `E`, which defaults to `()`. Nodes are identified by ids of
+/// type `Id`, which defaults to `u32`; see [`NodeId`].
+/// A graph created with [`Graph::new_multigraph`] keeps parallel edges between
+/// the same two nodes instead of replacing them.
+///
+/// # Example
+/// ```
+/// use graphs::{Graph, Node};
+///
+/// let mut graph: Graph<(), f32, (), &str> = Graph::new(5, false);
+/// graph.insert_node(Node::new("alice")).unwrap();
+/// graph.insert_node(Node::new("bob")).unwrap();
+/// graph.insert_edge("alice", "bob", 1.0).unwrap();
+/// assert!(graph.is_edge("alice", "bob"));
+/// ```
+pub struct Graph<T, W = f32, E = (), Id = u32> {
     pub capacity: usize,
-    pub nodes: Vec<Node<T>>,
+    pub nodes: Vec<Node<T, W, E, Id>>,
     pub undirected: bool,
+    multigraph: bool,
+    self_loops: SelfLoops,
+    index: HashMap<Id, usize>,
+    next_edge_id: u64,
+    next_id: Option<Id>,
+    free_ids: Vec<Id>,
+    reuse_ids: bool,
+    growth: GrowthPolicy,
+    generations: HashMap<Id, u64>,
+    next_generation: u64,
+    incoming: HashMap<Id, HashMap<Id, usize>>,
+    observers: Vec<BoxedObserver<T, W, E, Id>>,
+    weight_validator: Option<WeightValidator<W>>,
+    labels: Option<LabelIndex<T, Id>>,
+    schema: Option<SchemaCheck<T, W, E, Id>>,
 }
 
-impl<T> Graph<T> {
+impl<T, W: Weight, E, Id: NodeId> Graph<T, W, E, Id> {
     /// Creates a new grpah with the given capacity and directional type.
     pub fn new(capacity: usize, undirected: bool) -> Self {
         Self {
             capacity,
             nodes: Vec::with_capacity(capacity),
             undirected,
+            multigraph: false,
+            self_loops: SelfLoops::default(),
+            index: HashMap::with_capacity(capacity),
+            next_edge_id: 0,
+            next_id: None,
+            free_ids: Vec::new(),
+            reuse_ids: false,
+            growth: GrowthPolicy::Fixed,
+            generations: HashMap::new(),
+            next_generation: 0,
+            incoming: HashMap::new(),
+            observers: Vec::new(),
+            weight_validator: None,
+            labels: None,
+            schema: None,
+        }
+    }
+    /// Creates a new multigraph with the given capacity and directional type.
+    /// A multigraph allows parallel edges: inserting an edge between two nodes
+    /// that are already connected adds another edge with its own [`EdgeId`].
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut flights: Graph<&str> = Graph::new_multigraph(5, false);
+    /// flights.insert_node(Node::with_label(1, "OSL")).unwrap();
+    /// flights.insert_node(Node::with_label(2, "CPH")).unwrap();
+    ///
+    /// let morning = flights.add_edge(1, 2, 70.0).unwrap();
+    /// let evening = flights.add_edge(1, 2, 75.0).unwrap();
+    /// assert_eq!(flights.edges_between(1, 2).count(), 2);
+    ///
+    /// flights.remove_edge_by_id(1, 2, morning).unwrap();
+    /// assert_eq!(flights.get_edge(1, 2).unwrap().id(), evening);
+    /// ```
+    pub fn new_multigraph(capacity: usize, undirected: bool) -> Self {
+        Self {
+            multigraph: true,
+            ..Self::new(capacity, undirected)
         }
     }
+    /// Returns `true` if the graph allows parallel edges.
+    pub fn is_multigraph(&self) -> bool {
+        self.multigraph
+    }
+    /// Sets how the graph treats self-loops inserted through
+    /// [`Graph::insert_edge`] and friends. Self-loops are allowed by default.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::errors::GraphError;
+    /// use graphs::{Graph, Node, SelfLoops};
+    ///
+    /// let mut graph: Graph<()> = Graph::new(5, false).with_self_loops(SelfLoops::Reject);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// assert!(matches!(
+    ///     graph.insert_edge(1, 1, 1.0),
+    ///     Err(GraphError::SelfLoop { idx: 1 })
+    /// ));
+    /// ```
+    pub fn with_self_loops(mut self, policy: SelfLoops) -> Self {
+        self.self_loops = policy;
+        self
+    }
+    /// Returns the self-loop policy of the graph.
+    pub fn self_loops(&self) -> SelfLoops {
+        self.self_loops
+    }
+    /// Adds a check that every inserted or updated edge weight must pass, on
+    /// top of [`Weight::is_valid`]. Rejected weights fail with
+    /// `GraphError::InvalidWeight`. Edges already in the graph aren't checked.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<()> = Graph::new(5, false).with_weight_validator(|w| *w >= 0.0);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// graph.insert_node(Node::new(2)).unwrap();
+    /// assert!(graph.insert_edge(1, 2, -1.0).is_err());
+    /// ```
+    pub fn with_weight_validator(
+        mut self,
+        validator: impl Fn(&W) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.weight_validator = Some(Box::new(validator));
+        self
+    }
+    /// Sets how the graph grows once `capacity` nodes have been inserted.
+    /// Graphs have a fixed capacity by default.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, GrowthPolicy, Node};
+    ///
+    /// let mut graph: Graph<()> = Graph::new(1, false).with_growth_policy(GrowthPolicy::Doubling);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// graph.insert_node(Node::new(2)).unwrap();
+    /// assert_eq!(graph.capacity, 2);
+    /// ```
+    pub fn with_growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = policy;
+        self
+    }
+    /// Returns the growth policy of the graph.
+    pub fn growth_policy(&self) -> &GrowthPolicy {
+        &self.growth
+    }
+    /// Reserves room for at least `additional` more nodes, raising `capacity`
+    /// to match if needed.
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.index.reserve(additional);
+        self.capacity = self.capacity.max(self.nodes.len() + additional);
+    }
+    /// Releases unused room and lowers `capacity` to the current number of
+    /// nodes. With a fixed growth policy, the graph is full afterwards.
+    pub fn shrink_to_fit(&mut self) {
+        self.nodes.shrink_to_fit();
+        self.index.shrink_to_fit();
+        self.capacity = self.nodes.len();
+    }
     /// Returns `true` if the graph contains no nodes.
     pub fn is_empty(&self) -> bool {
         self.nodes.is_empty()
@@ -125,64 +520,960 @@ impl<T> Graph<T> {
     pub fn len(&self) -> usize {
         self.nodes.len()
     }
+    /// Returns an iterator over the nodes of the graph, in insertion order
+    /// (node removal moves the last node into the freed slot).
+    pub fn nodes(&self) -> std::slice::Iter<'_, Node<T, W, E, Id>> {
+        self.nodes.iter()
+    }
+    /// Returns an iterator over mutable references to the nodes of the graph.
+    /// Changing a node's id or its edges through it bypasses the graph's
+    /// indexes; use it to update labels.
+    pub fn nodes_mut(&mut self) -> std::slice::IterMut<'_, Node<T, W, E, Id>> {
+        self.nodes.iter_mut()
+    }
+    /// Returns an iterator over all edges as `(from, to, &weight)`.
+    /// In an undirected graph each edge is yielded once, in one of its two
+    /// directions. Parallel edges in a multigraph are yielded one by one.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<()> = Graph::new(5, true);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// graph.insert_node(Node::new(2)).unwrap();
+    /// graph.insert_edge(1, 2, 3.0).unwrap();
+    ///
+    /// let edges: Vec<_> = graph.edges().collect();
+    /// assert_eq!(edges, vec![(&1, &2, &3.0)]);
+    /// ```
+    pub fn edges(&self) -> impl Iterator<Item = (&Id, &Id, &W)> {
+        self.edge_refs()
+            .map(|edge| (&edge.from_node, &edge.to_node, &edge.weight))
+    }
     /// Inserts an edge between two nodes in the graph.
     /// If the edge already exists, updates the edge details and returns the
-    /// old value. Otherwise returns `Ok(None)`.
+    /// old value. Otherwise returns `Ok(None)`. In a multigraph a new parallel
+    /// edge is always added.
+    /// Both nodes must already be in the graph, otherwise the error names the
+    /// missing endpoint. A weight that isn't [`Weight::is_valid`], such as
+    /// a `NaN` float, is rejected with `GraphError::InvalidWeight`. If a
+    /// [`Schema`](schema::Schema) is attached, the edge must satisfy it.
+    /// In an undirected graph the edge is mirrored from `to` back to `from`,
+    /// so both directions always carry the same weight.
+    /// A self-loop is handled according to the graph's [`SelfLoops`] policy;
+    /// an ignored self-loop returns `Ok(None)` without inserting anything.
+    /// The payload of the edge is set to its default value.
     pub fn insert_edge(
         &mut self,
-        from: u32,
-        to: u32,
-        weight: f32,
-    ) -> Result<Option<Edge>, GraphError> {
-        if let Some(src_node_idx) = self.nodes.iter().position(|n| n.idx == from) {
-            Ok(self.nodes[src_node_idx].add_edge(to, weight))
-        } else {
-            Err(GraphError::MissingNode)
+        from: Id,
+        to: Id,
+        weight: W,
+    ) -> Result<Option<Edge<W, E, Id>>, GraphError<Id>>
+    where
+        E: Default + Clone,
+    {
+        self.insert_edge_with_data(from, to, weight, E::default())
+    }
+    /// Inserts an edge carrying `data` between two nodes in the graph.
+    /// Behaves like [`Graph::insert_edge`] otherwise; in an undirected graph
+    /// the mirrored edge gets a clone of `data`.
+    pub fn insert_edge_with_data(
+        &mut self,
+        from: Id,
+        to: Id,
+        weight: W,
+        data: E,
+    ) -> Result<Option<Edge<W, E, Id>>, GraphError<Id>>
+    where
+        E: Clone,
+    {
+        Ok(self
+            .connect(from, to, weight, data)?
+            .and_then(|(_, old)| old))
+    }
+    /// Adds an edge between two nodes in the graph and returns its id.
+    /// In a multigraph this always adds a new parallel edge. Otherwise it
+    /// behaves like [`Graph::insert_edge`] and returns the id of the inserted
+    /// or updated edge.
+    /// Because no edge is created, a self-loop fails with
+    /// `GraphError::SelfLoop` under both the `Reject` and `Ignore` policies.
+    pub fn add_edge(&mut self, from: Id, to: Id, weight: W) -> Result<EdgeId, GraphError<Id>>
+    where
+        E: Default + Clone,
+    {
+        self.add_edge_with_data(from, to, weight, E::default())
+    }
+    /// Adds an edge carrying `data` between two nodes in the graph and returns
+    /// its id. Behaves like [`Graph::add_edge`] otherwise.
+    pub fn add_edge_with_data(
+        &mut self,
+        from: Id,
+        to: Id,
+        weight: W,
+        data: E,
+    ) -> Result<EdgeId, GraphError<Id>>
+    where
+        E: Clone,
+    {
+        let idx = from.clone();
+        self.connect(from, to, weight, data)?
+            .map(|(id, _)| id)
+            .ok_or(GraphError::SelfLoop { idx })
+    }
+    /// Creates a directed graph from `(from, to, weight)` triples, adding an
+    /// unlabelled node for every endpoint. The graph starts empty and uses
+    /// [`GrowthPolicy::Doubling`].
+    /// Fails if an edge is rejected, for example because of an invalid weight.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::Graph;
+    ///
+    /// let graph: Graph<()> = Graph::from_edges([(1, 2, 1.0), (2, 3, 2.5)]).unwrap();
+    /// assert_eq!(graph.len(), 3);
+    /// assert!(graph.is_edge(2, 3));
+    /// ```
+    pub fn from_edges<I>(edges: I) -> Result<Self, GraphError<Id>>
+    where
+        I: IntoIterator<Item = (Id, Id, W)>,
+        E: Default + Clone,
+    {
+        let mut graph = Self::new(0, false).with_growth_policy(GrowthPolicy::Doubling);
+        graph.insert_edges(edges)?;
+        Ok(graph)
+    }
+    /// Inserts every `(from, to, weight)` triple like [`Graph::insert_edge`],
+    /// first inserting an unlabelled node for any endpoint that isn't in the
+    /// graph yet. New nodes are subject to the graph's growth policy.
+    /// Unless the growth policy is [`GrowthPolicy::Fixed`], room for one node
+    /// per edge in the iterator's size hint is reserved up front, so the graph
+    /// doesn't grow again and again while loading.
+    /// Stops at the first error; edges inserted before it are kept.
+    pub fn insert_edges<I>(&mut self, edges: I) -> Result<(), GraphError<Id>>
+    where
+        I: IntoIterator<Item = (Id, Id, W)>,
+        E: Default + Clone,
+    {
+        let edges = edges.into_iter();
+        if !matches!(self.growth, GrowthPolicy::Fixed) {
+            self.reserve(edges.size_hint().0);
         }
+        for (from, to, weight) in edges {
+            for idx in [&from, &to] {
+                if !self.index.contains_key(idx) {
+                    self.insert_node(Node::new(idx.clone()))?;
+                }
+            }
+            self.connect(from, to, weight, E::default())?;
+        }
+        Ok(())
     }
     /// Inserts a node in the graph.
-    pub fn insert_node(&mut self, node: Node<T>) -> Result<(), GraphError> {
-        if self.nodes.len() == self.capacity {
-            Err(GraphError::CapacityExceeded)
-        } else {
-            self.nodes.push(node);
-            Ok(())
+    /// Returns `GraphError::DuplicateNode` if a node with the same index
+    /// number already exists. Use [`Graph::insert_or_merge_node`] to combine
+    /// the two nodes instead.
+    /// Edges already attached to the node are inserted like
+    /// [`Graph::insert_edge`] does, so they get fresh edge ids and are
+    /// mirrored in an undirected graph. Their targets must be in the graph,
+    /// or be the node itself, otherwise the node is rejected with
+    /// `GraphError::MissingTargetNode`. Attached self-loops follow the
+    /// graph's [`SelfLoops`] policy, and their weights are checked like those
+    /// given to [`Graph::insert_edge`].
+    /// If a [`Schema`](schema::Schema) is attached, the node must satisfy it.
+    pub fn insert_node(&mut self, mut node: Node<T, W, E, Id>) -> Result<(), GraphError<Id>>
+    where
+        E: Clone,
+    {
+        if self.index.contains_key(&node.idx) {
+            return Err(GraphError::DuplicateNode { idx: node.idx });
+        }
+        self.check_attached_edges(&node, &node.edges)?;
+        let idx = node.idx.clone();
+        let edges = std::mem::take(&mut node.edges);
+        self.check_schema(&node, &edges)?;
+        self.push_node(node)?;
+        for (to, edges) in edges {
+            for edge in edges {
+                self.connect(idx.clone(), to.clone(), edge.weight, edge.data)?;
+            }
+        }
+        Ok(())
+    }
+    /// Adds a checked node that isn't in the graph yet and has no edges, once
+    /// it fits the capacity.
+    fn push_node(&mut self, node: Node<T, W, E, Id>) -> Result<(), GraphError<Id>> {
+        if self.nodes.len() >= self.capacity {
+            self.grow()?;
+        }
+        if let (Some(labels), Some(label)) = (self.labels.as_mut(), &node.label) {
+            labels.insert(label, node.idx.clone());
+        }
+        self.index.insert(node.idx.clone(), self.nodes.len());
+        self.generations
+            .insert(node.idx.clone(), self.next_generation);
+        self.next_generation += 1;
+        self.nodes.push(node);
+        for observer in self.observers.iter_mut() {
+            observer.on_node_inserted(&self.nodes[self.nodes.len() - 1]);
+        }
+        if let GrowthPolicy::Unbounded {
+            soft_limit,
+            on_soft_limit,
+        } = &mut self.growth
+        {
+            self.capacity = self.nodes.capacity();
+            if self.nodes.len() == *soft_limit + 1 {
+                on_soft_limit(self.nodes.len());
+            }
+        }
+        Ok(())
+    }
+    /// Inserts a node in the graph, or merges it into the existing node with
+    /// the same index number.
+    /// When merging, `merge` receives the existing label and the new label
+    /// and returns the label to keep. Edges of the new node are then inserted
+    /// like [`Graph::insert_edge`] does, so they replace edges to the same
+    /// neighbor outside a multigraph and are mirrored in an undirected graph.
+    /// They are checked like the edges passed to [`Graph::insert_node`]
+    /// before anything changes.
+    /// If a schema is attached, the merged label and the new edges must
+    /// satisfy it, otherwise the existing node is left unchanged.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<u32> = Graph::new(5, false);
+    /// graph.insert_node(Node::with_label(1, 10)).unwrap();
+    ///
+    /// // Sum the labels of duplicate nodes.
+    /// let sum = |old: Option<u32>, new: Option<u32>| Some(old.unwrap_or(0) + new.unwrap_or(0));
+    /// graph.insert_or_merge_node(Node::with_label(1, 5), sum).unwrap();
+    /// assert_eq!(graph.len(), 1);
+    /// assert_eq!(graph.nodes[0].label, Some(15));
+    /// ```
+    pub fn insert_or_merge_node<F>(
+        &mut self,
+        mut node: Node<T, W, E, Id>,
+        merge: F,
+    ) -> Result<(), GraphError<Id>>
+    where
+        E: Clone,
+        F: FnOnce(Option<T>, Option<T>) -> Option<T>,
+    {
+        let Some(&pos) = self.index.get(&node.idx) else {
+            return self.insert_node(node);
+        };
+        self.check_attached_edges(&self.nodes[pos], &node.edges)?;
+        let idx = node.idx.clone();
+        let edges = std::mem::take(&mut node.edges);
+        let existing = &mut self.nodes[pos];
+        let backup = match (&self.schema, &existing.label) {
+            (Some(schema), Some(label)) => Some(schema.clone_label(label)),
+            _ => None,
+        };
+        if let (Some(labels), Some(label)) = (self.labels.as_mut(), &existing.label) {
+            labels.remove(label, &idx);
+        }
+        existing.label = merge(existing.label.take(), node.label);
+        let checked = self.check_schema(&self.nodes[pos], &edges);
+        if checked.is_err() {
+            self.nodes[pos].label = backup;
+        }
+        if let (Some(labels), Some(label)) = (self.labels.as_mut(), &self.nodes[pos].label) {
+            labels.insert(label, idx.clone());
+        }
+        checked?;
+        for observer in self.observers.iter_mut() {
+            observer.on_node_updated(&self.nodes[pos]);
+        }
+        for (to, edges) in edges {
+            for edge in edges {
+                self.connect(idx.clone(), to.clone(), edge.weight, edge.data)?;
+            }
+        }
+        Ok(())
+    }
+    /// Removes a node from the graph and returns it along with its label.
+    /// Every edge in the graph that points at the removed node is removed as
+    /// well. Returns `GraphError::MissingNode` if the node doesn't exist.
+    pub fn remove_node(&mut self, idx: Id) -> Result<Node<T, W, E, Id>, GraphError<Id>> {
+        let Some(pos) = self.index.remove(&idx) else {
+            return Err(GraphError::MissingNode { idx });
+        };
+        let node = self.nodes.swap_remove(pos);
+        if let (Some(labels), Some(label)) = (self.labels.as_mut(), &node.label) {
+            labels.remove(label, &idx);
+        }
+        // The last node was moved into the freed slot.
+        if let Some(moved) = self.nodes.get(pos) {
+            self.index.insert(moved.idx.clone(), pos);
+        }
+        for pred in self.incoming.remove(&idx).unwrap_or_default().into_keys() {
+            if let Some(other) = self.node_mut(&pred) {
+                other.edges.remove(&idx);
+            }
+        }
+        for (to, edges) in node.edges.iter() {
+            self.unlink(&idx, to, edges.len());
+        }
+        self.generations.remove(&idx);
+        if self.reuse_ids {
+            self.free_ids.push(idx);
         }
+        for observer in self.observers.iter_mut() {
+            observer.on_node_removed(&node);
+        }
+        Ok(node)
+    }
+    /// Returns a reference to a node if it exists in the graph.
+    pub fn get_node(&self, idx: Id) -> Option<&Node<T, W, E, Id>> {
+        self.node(&idx)
+    }
+    /// Returns a mutable reference to a node, or `GraphError::MissingNode` if
+    /// it doesn't exist. Changing the node's `idx` through it breaks lookups.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::errors::GraphError;
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<&str> = Graph::new(5, false);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// graph.get_node_mut(1).unwrap().label = Some("one");
+    /// assert_eq!(graph.get_node(1).unwrap().label, Some("one"));
+    /// assert_eq!(graph.get_node_mut(2).err(), Some(GraphError::MissingNode { idx: 2 }));
+    /// ```
+    pub fn get_node_mut(&mut self, idx: Id) -> Result<&mut Node<T, W, E, Id>, GraphError<Id>> {
+        match self.index.get(&idx) {
+            Some(&pos) => Ok(&mut self.nodes[pos]),
+            None => Err(GraphError::MissingNode { idx }),
+        }
+    }
+    /// Returns a generational handle to a node if it exists in the graph.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::errors::GraphError;
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<&str> = Graph::new(5, false);
+    /// graph.insert_node(Node::with_label(1, "old")).unwrap();
+    /// let handle = graph.handle(1).unwrap();
+    ///
+    /// graph.remove_node(1).unwrap();
+    /// graph.insert_node(Node::with_label(1, "new")).unwrap();
+    /// assert!(matches!(
+    ///     graph.get_node_by_handle(&handle),
+    ///     Err(GraphError::StaleHandle { idx: 1 })
+    /// ));
+    /// ```
+    pub fn handle(&self, idx: Id) -> Option<NodeHandle<Id>> {
+        let generation = *self.generations.get(&idx)?;
+        Some(NodeHandle { idx, generation })
+    }
+    /// Returns `true` if the node the handle refers to is still in the graph.
+    pub fn contains_handle(&self, handle: &NodeHandle<Id>) -> bool {
+        self.generations.get(&handle.idx) == Some(&handle.generation)
+    }
+    /// Returns a reference to the node the handle refers to, or
+    /// `GraphError::StaleHandle` if that node has been removed.
+    pub fn get_node_by_handle(
+        &self,
+        handle: &NodeHandle<Id>,
+    ) -> Result<&Node<T, W, E, Id>, GraphError<Id>> {
+        if !self.contains_handle(handle) {
+            return Err(GraphError::StaleHandle {
+                idx: handle.idx.clone(),
+            });
+        }
+        Ok(&self.nodes[self.index[&handle.idx]])
+    }
+    /// Returns a mutable reference to the node the handle refers to, or
+    /// `GraphError::StaleHandle` if that node has been removed.
+    pub fn get_node_mut_by_handle(
+        &mut self,
+        handle: &NodeHandle<Id>,
+    ) -> Result<&mut Node<T, W, E, Id>, GraphError<Id>> {
+        if !self.contains_handle(handle) {
+            return Err(GraphError::StaleHandle {
+                idx: handle.idx.clone(),
+            });
+        }
+        let pos = self.index[&handle.idx];
+        Ok(&mut self.nodes[pos])
+    }
+    /// Removes the node the handle refers to, like [`Graph::remove_node`], or
+    /// returns `GraphError::StaleHandle` if that node has already been removed.
+    pub fn remove_node_by_handle(
+        &mut self,
+        handle: &NodeHandle<Id>,
+    ) -> Result<Node<T, W, E, Id>, GraphError<Id>> {
+        if !self.contains_handle(handle) {
+            return Err(GraphError::StaleHandle {
+                idx: handle.idx.clone(),
+            });
+        }
+        self.remove_node(handle.idx.clone())
     }
     /// Check if a node exists in the graph by its index number.
-    pub fn has_node(&self, idx: u32) -> bool {
-        self.nodes.iter().any(|n| n.idx == idx)
+    pub fn has_node(&self, idx: Id) -> bool {
+        self.index.contains_key(&idx)
+    }
+    /// Returns the number of edges leaving a node, or `None` if the node
+    /// doesn't exist. A self-loop counts once.
+    pub fn out_degree(&self, idx: Id) -> Option<usize> {
+        self.node(&idx).map(Node::number_of_edges)
+    }
+    /// Returns the degree of a node, or `None` if the node doesn't exist.
+    /// In an undirected graph this is the number of incident edges; in a
+    /// directed graph it is the number of outgoing plus incoming edges.
+    /// Either way, a self-loop adds two to the degree.
+    pub fn degree(&self, idx: Id) -> Option<usize> {
+        let node = self.node(&idx)?;
+        let loops = node.edges.get(&idx).map_or(0, Vec::len);
+        if self.undirected {
+            // Self-loops are stored once, but touch the node at both ends.
+            Some(node.number_of_edges() + loops)
+        } else {
+            Some(node.number_of_edges() + self.in_degree(idx)?)
+        }
+    }
+    /// Returns the number of edges entering a node, or `None` if the node
+    /// doesn't exist. A self-loop counts once.
+    pub fn in_degree(&self, idx: Id) -> Option<usize> {
+        if !self.index.contains_key(&idx) {
+            return None;
+        }
+        Some(
+            self.incoming
+                .get(&idx)
+                .map_or(0, |preds| preds.values().sum()),
+        )
+    }
+    /// Returns an iterator over the ids of the nodes that have an edge to
+    /// `idx`. Each predecessor is yielded once, even if it has parallel edges
+    /// to `idx`. In an undirected graph these are the node's neighbors.
+    pub fn predecessors(&self, idx: Id) -> impl Iterator<Item = &Id> {
+        self.incoming.get(&idx).into_iter().flat_map(HashMap::keys)
+    }
+    /// Returns an iterator over the ids of the nodes that `idx` has an edge
+    /// to. Each neighbor is yielded once, even if there are parallel edges to
+    /// it. In an undirected graph these are all adjacent nodes.
+    pub fn neighbors(&self, idx: Id) -> impl Iterator<Item = &Id> {
+        self.node(&idx)
+            .into_iter()
+            .flat_map(|node| node.edges.keys())
+    }
+    /// Returns an iterator over mutable references to the nodes that `idx`
+    /// has an edge to, in the order they are stored in `nodes`.
+    pub fn neighbors_mut(&mut self, idx: Id) -> impl Iterator<Item = &mut Node<T, W, E, Id>> {
+        let mut slots: Vec<usize> = self
+            .node(&idx)
+            .into_iter()
+            .flat_map(|node| node.edges.keys())
+            .filter_map(|neighbor| self.index.get(neighbor).copied())
+            .collect();
+        slots.sort_unstable();
+        let mut nodes = self.nodes.iter_mut();
+        let mut next = 0;
+        slots.into_iter().filter_map(move |slot| {
+            let node = nodes.nth(slot - next);
+            next = slot + 1;
+            node
+        })
+    }
+    /// Returns an iterator over the edges entering `idx`.
+    pub fn incoming_edges(&self, idx: Id) -> impl Iterator<Item = &Edge<W, E, Id>> {
+        self.incoming
+            .get(&idx)
+            .into_iter()
+            .flat_map(HashMap::keys)
+            .filter_map(move |pred| self.node(pred)?.edges.get(&idx))
+            .flatten()
     }
     /// Checks if an edge exists between two nodes.
-    pub fn has_edge(&self, from: u32, to: u32) -> bool {
+    pub fn has_edge(&self, from: Id, to: Id) -> bool {
         self.get_edge(from, to).is_some()
     }
     /// Returns a reference to the `Edge` object if it exists between two nodes
-    /// in the graph.
-    pub fn get_edge(&self, from: u32, to: u32) -> Option<&Edge> {
-        if let Some(src_node_idx) = self.nodes.iter().position(|n| n.idx == from) {
-            self.nodes[src_node_idx].get_edge(to)
-        } else {
-            None
+    /// in the graph. If there are parallel edges, returns the oldest one.
+    pub fn get_edge(&self, from: Id, to: Id) -> Option<&Edge<W, E, Id>> {
+        self.edges_between(from, to).next()
+    }
+    /// Returns a reference to the `Edge` object with the given id between two
+    /// nodes in the graph.
+    pub fn get_edge_by_id(&self, from: Id, to: Id, id: EdgeId) -> Option<&Edge<W, E, Id>> {
+        self.edges_between(from, to).find(|edge| edge.id == id)
+    }
+    /// Returns an iterator over all edges from `from` to `to`, oldest first.
+    /// Outside of a multigraph it yields at most one edge.
+    pub fn edges_between(&self, from: Id, to: Id) -> impl Iterator<Item = &Edge<W, E, Id>> {
+        self.node(&from)
+            .and_then(|node| node.edges.get(&to))
+            .into_iter()
+            .flatten()
+    }
+    /// Returns a mutable handle to the `Edge` object if it exists between two
+    /// nodes in the graph. Use it to update the weight or payload of the edge
+    /// in place.
+    /// If there are parallel edges, returns the oldest one.
+    /// In an undirected graph, changes are copied to the mirrored edge from
+    /// `to` to `from` when the handle is dropped.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<(), f32, &str> = Graph::new(5, true);
+    /// graph.insert_node(Node::new(1)).unwrap();
+    /// graph.insert_node(Node::new(2)).unwrap();
+    /// graph.insert_edge_with_data(1, 2, 1.0, "knows").unwrap();
+    ///
+    /// *graph.get_edge_mut(1, 2).unwrap().data_mut() = "works_with";
+    /// assert_eq!(*graph.get_edge(2, 1).unwrap().data(), "works_with");
+    /// ```
+    pub fn get_edge_mut(&mut self, from: Id, to: Id) -> Option<EdgeMut<'_, W, E, Id>>
+    where
+        E: Clone,
+    {
+        let id = self.get_edge(from.clone(), to.clone())?.id;
+        self.edge_mut(&from, &to, id)
+    }
+    /// Replaces the weight of the edge from `from` to `to` and returns the old
+    /// weight. If there are parallel edges, the oldest one is updated.
+    /// In an undirected graph the mirrored edge is updated as well.
+    /// Fails if either node or the edge is missing, or with
+    /// `GraphError::InvalidWeight` if the weight isn't [`Weight::is_valid`].
+    pub fn update_weight(&mut self, from: Id, to: Id, weight: W) -> Result<W, GraphError<Id>> {
+        if !self.index.contains_key(&from) {
+            return Err(GraphError::MissingSourceNode { idx: from });
+        }
+        if !self.index.contains_key(&to) {
+            return Err(GraphError::MissingTargetNode { idx: to });
         }
+        if !self.accepts(&weight) {
+            return Err(GraphError::InvalidWeight { from, to });
+        }
+        let Some(id) = self.get_edge(from.clone(), to.clone()).map(Edge::id) else {
+            return Err(GraphError::MissingEdge { from, to });
+        };
+        if self.undirected && from != to {
+            if let Some(mirror) = self.node_mut(&to).and_then(|node| node.edge_mut(&from, id)) {
+                mirror.weight = weight.clone();
+            }
+        }
+        let src_node_idx = self.index[&from];
+        let edge = self.nodes[src_node_idx]
+            .edge_mut(&to, id)
+            .ok_or(GraphError::MissingEdge { from, to })?;
+        let old = std::mem::replace(&mut edge.weight, weight);
+        for observer in self.observers.iter_mut() {
+            observer.on_edge_updated(edge);
+        }
+        Ok(old)
     }
     /// Checks whether an edge exists between two nodes in the graph.
-    pub fn is_edge(&self, from: u32, to: u32) -> bool {
+    pub fn is_edge(&self, from: Id, to: Id) -> bool {
         self.get_edge(from, to).is_some()
     }
     /// Removes an edge between the two specified nodes.
     /// Returns the index of the neighbor node and the edge value if the edge
-    /// existed between the two nodes.
-    pub fn remove_edge(&mut self, from: u32, to: u32) -> Option<(u32, Edge)> {
-        if !self.has_node(from) || !self.has_node(to) {
-            return None;
+    /// existed between the two nodes, otherwise `GraphError::MissingEdge`.
+    /// If there are parallel edges, only the oldest one is removed.
+    /// In an undirected graph the mirrored edge from `to` to `from` is removed
+    /// as well.
+    pub fn remove_edge(&mut self, from: Id, to: Id) -> Result<Removed<W, E, Id>, GraphError<Id>> {
+        if !self.index.contains_key(&from) {
+            return Err(GraphError::MissingSourceNode { idx: from });
+        }
+        if !self.index.contains_key(&to) {
+            return Err(GraphError::MissingTargetNode { idx: to });
+        }
+        let Some(id) = self.get_edge(from.clone(), to.clone()).map(Edge::id) else {
+            return Err(GraphError::MissingEdge { from, to });
+        };
+        let edge = self.remove_edge_by_id(from, to.clone(), id)?;
+        Ok((to, edge))
+    }
+    /// Removes the edge with the given id between the two specified nodes and
+    /// returns it. Other parallel edges between the nodes are kept.
+    /// In an undirected graph the mirrored edge is removed as well.
+    pub fn remove_edge_by_id(
+        &mut self,
+        from: Id,
+        to: Id,
+        id: EdgeId,
+    ) -> Result<Edge<W, E, Id>, GraphError<Id>> {
+        let Some(removed) = self
+            .node_mut(&from)
+            .and_then(|node| node.take_edge(&to, id))
+        else {
+            return Err(GraphError::MissingEdge { from, to });
+        };
+        self.unlink(&from, &to, 1);
+        if self.undirected && from != to {
+            if let Some(mirror) = self
+                .node_mut(&to)
+                .and_then(|node| node.take_edge(&from, id))
+            {
+                self.unlink(&mirror.from_node, &mirror.to_node, 1);
+            }
+        }
+        for observer in self.observers.iter_mut() {
+            observer.on_edge_removed(&removed);
+        }
+        Ok(removed)
+    }
+    /// Inserts or updates an edge, mirroring it in an undirected graph.
+    /// Returns the id of the edge and the replaced edge, if any, or `None` if
+    /// the edge is a self-loop ignored by the graph's policy.
+    fn connect(
+        &mut self,
+        from: Id,
+        to: Id,
+        weight: W,
+        data: E,
+    ) -> Result<Option<Connected<W, E, Id>>, GraphError<Id>>
+    where
+        E: Clone,
+    {
+        let Some(&src_node_idx) = self.index.get(&from) else {
+            return Err(GraphError::MissingSourceNode { idx: from });
+        };
+        let Some(&dst_node_idx) = self.index.get(&to) else {
+            return Err(GraphError::MissingTargetNode { idx: to });
+        };
+        if !self.accepts(&weight) {
+            return Err(GraphError::InvalidWeight { from, to });
+        }
+        if from == to {
+            match self.self_loops {
+                SelfLoops::Allow => {}
+                SelfLoops::Reject => return Err(GraphError::SelfLoop { idx: from }),
+                SelfLoops::Ignore => return Ok(None),
+            }
+        }
+        if let Some(schema) = &self.schema {
+            self.check_schema_edge(
+                schema,
+                &self.nodes[src_node_idx],
+                &self.nodes[dst_node_idx],
+                &data,
+            )?;
+        }
+        let id = match self.nodes[src_node_idx]
+            .edges
+            .get(&to)
+            .and_then(|e| e.first())
+        {
+            Some(edge) if !self.multigraph => edge.id,
+            _ => self.next_edge_id(),
+        };
+        if self.undirected && from != to {
+            let mut mirror =
+                Edge::with_data(to.clone(), from.clone(), weight.clone(), data.clone());
+            mirror.id = id;
+            if self.nodes[dst_node_idx]
+                .put_edge(mirror, self.multigraph)
+                .is_none()
+            {
+                self.link(&to, &from, 1);
+            }
+        }
+        let mut edge = Edge::with_data(from.clone(), to.clone(), weight, data);
+        edge.id = id;
+        let old = self.nodes[src_node_idx].put_edge(edge, self.multigraph);
+        if old.is_none() {
+            self.link(&from, &to, 1);
         }
-        let node = self.get_node_mut(from).unwrap();
-        node.remove_edge(to)
+        if let Some(edge) = self.nodes[src_node_idx].edge_mut(&to, id) {
+            for observer in self.observers.iter_mut() {
+                match old {
+                    Some(_) => observer.on_edge_updated(edge),
+                    None => observer.on_edge_inserted(edge),
+                }
+            }
+        }
+        Ok(Some((id, old)))
+    }
+    /// Returns a mutable handle to the edge with the given id.
+    fn edge_mut(&mut self, from: &Id, to: &Id, id: EdgeId) -> Option<EdgeMut<'_, W, E, Id>>
+    where
+        E: Clone,
+    {
+        let &src_node_idx = self.index.get(from)?;
+        let mirror_idx = match self.index.get(to) {
+            Some(&dst_node_idx) if self.undirected && dst_node_idx != src_node_idx => {
+                Some(dst_node_idx)
+            }
+            _ => None,
+        };
+        let (edge, mirror) = match mirror_idx {
+            Some(dst_node_idx) => {
+                let (src, dst) = self.two_nodes_mut(src_node_idx, dst_node_idx);
+                (src.edge_mut(to, id)?, dst.edge_mut(from, id))
+            }
+            None => (self.nodes[src_node_idx].edge_mut(to, id)?, None),
+        };
+        Some(EdgeMut { edge, mirror })
+    }
+    /// Returns an iterator over all edges, yielding each undirected edge once.
+    pub(crate) fn edge_refs(&self) -> impl Iterator<Item = &Edge<W, E, Id>> {
+        self.nodes.iter().enumerate().flat_map(move |(slot, node)| {
+            node.edges
+                .iter()
+                .filter(move |(to, _)| {
+                    !self.undirected || self.index.get(*to).is_none_or(|&other| other >= slot)
+                })
+                .flat_map(|(_, edges)| edges)
+        })
+    }
+    /// Checks `edges`, attached to `from` outside the graph, before they are
+    /// added to the graph along with `from`.
+    fn check_attached_edges(
+        &self,
+        from: &Node<T, W, E, Id>,
+        edges: &HashMap<Id, Vec<Edge<W, E, Id>>>,
+    ) -> Result<(), GraphError<Id>> {
+        for (to, edges) in edges {
+            if edges.iter().any(|edge| !self.accepts(&edge.weight)) {
+                return Err(GraphError::InvalidWeight {
+                    from: from.idx.clone(),
+                    to: to.clone(),
+                });
+            }
+            if *to == from.idx {
+                if self.self_loops == SelfLoops::Reject {
+                    return Err(GraphError::SelfLoop { idx: to.clone() });
+                }
+            } else if !self.index.contains_key(to) {
+                return Err(GraphError::MissingTargetNode { idx: to.clone() });
+            }
+        }
+        Ok(())
+    }
+    /// Checks a weight against [`Weight::is_valid`] and the weight validator.
+    fn accepts(&self, weight: &W) -> bool {
+        weight.is_valid()
+            && self
+                .weight_validator
+                .as_ref()
+                .is_none_or(|validator| validator(weight))
+    }
+    /// Records `count` more edges from `from` to `to` in the incoming index.
+    fn link(&mut self, from: &Id, to: &Id, count: usize) {
+        if count > 0 {
+            *self
+                .incoming
+                .entry(to.clone())
+                .or_default()
+                .entry(from.clone())
+                .or_insert(0) += count;
+        }
+    }
+    /// Forgets `count` edges from `from` to `to` in the incoming index.
+    fn unlink(&mut self, from: &Id, to: &Id, count: usize) {
+        let Some(preds) = self.incoming.get_mut(to) else {
+            return;
+        };
+        if let Some(n) = preds.get_mut(from) {
+            *n = n.saturating_sub(count);
+            if *n == 0 {
+                preds.remove(from);
+            }
+        }
+        if preds.is_empty() {
+            self.incoming.remove(to);
+        }
+    }
+    /// Makes room for one more node according to the growth policy.
+    fn grow(&mut self) -> Result<(), GraphError<Id>> {
+        match self.growth {
+            GrowthPolicy::Fixed => Err(GraphError::CapacityExceeded {
+                capacity: self.capacity,
+            }),
+            GrowthPolicy::Doubling => {
+                let capacity = (self.capacity * 2).max(self.nodes.len() + 1);
+                self.nodes.reserve_exact(capacity - self.nodes.len());
+                self.index.reserve(capacity - self.nodes.len());
+                self.capacity = capacity;
+                Ok(())
+            }
+            GrowthPolicy::Unbounded { .. } => {
+                self.nodes.reserve(1);
+                self.capacity = self.nodes.capacity();
+                Ok(())
+            }
+        }
+    }
+    fn next_edge_id(&mut self) -> EdgeId {
+        let id = EdgeId(self.next_edge_id);
+        self.next_edge_id += 1;
+        id
+    }
+    /// Returns mutable references to the nodes at two distinct positions in
+    /// `nodes`.
+    fn two_nodes_mut(&mut self, a: usize, b: usize) -> NodePair<'_, T, W, E, Id> {
+        if a < b {
+            let (left, right) = self.nodes.split_at_mut(b);
+            (&mut left[a], &mut right[0])
+        } else {
+            let (left, right) = self.nodes.split_at_mut(a);
+            (&mut right[0], &mut left[b])
+        }
+    }
+    fn node(&self, idx: &Id) -> Option<&Node<T, W, E, Id>> {
+        self.index.get(idx).map(|&pos| &self.nodes[pos])
+    }
+    fn node_mut(&mut self, idx: &Id) -> Option<&mut Node<T, W, E, Id>> {
+        let &pos = self.index.get(idx)?;
+        Some(&mut self.nodes[pos])
+    }
+}
+
+impl<T, W: Weight, E, Id: SequentialId> Graph<T, W, E, Id> {
+    /// Sets whether [`Graph::add_node`] hands out ids of removed nodes again
+    /// before allocating new ones. Ids are not reused by default.
+    pub fn with_id_reuse(mut self, reuse: bool) -> Self {
+        self.reuse_ids = reuse;
+        self
+    }
+    /// Inserts a new node with `label` under a freshly allocated id and
+    /// returns the id.
+    /// Allocated ids never collide with ids inserted explicitly through
+    /// [`Graph::insert_node`], so both can be mixed freely.
+    /// Returns `GraphError::IdsExhausted` if every id of the type is in use.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::{Graph, Node};
+    ///
+    /// let mut graph: Graph<&str> = Graph::new(5, false).with_id_reuse(true);
+    /// graph.insert_node(Node::with_label(0, "explicit")).unwrap();
+    ///
+    /// let a = graph.add_node("a").unwrap();
+    /// let b = graph.add_node("b").unwrap();
+    /// assert_eq!((a, b), (1, 2));
+    ///
+    /// graph.remove_node(a).unwrap();
+    /// assert_eq!(graph.add_node("c").unwrap(), a);
+    /// ```
+    pub fn add_node(&mut self, label: T) -> Result<Id, GraphError<Id>> {
+        let idx = self.allocate_id().ok_or(GraphError::IdsExhausted)?;
+        let node = Node::with_label(idx.clone(), label);
+        if let Err(err) = self
+            .check_schema(&node, &HashMap::new())
+            .and_then(|()| self.push_node(node))
+        {
+            // The id was never used, so hand it out next time.
+            self.free_ids.push(idx);
+            return Err(err);
+        }
+        Ok(idx)
+    }
+    /// Returns an id that is not in use, preferring freed ids.
+    /// The id isn't reserved until a node is inserted with it.
+    fn allocate_id(&mut self) -> Option<Id> {
+        while let Some(idx) = self.free_ids.pop() {
+            if !self.index.contains_key(&idx) {
+                return Some(idx);
+            }
+        }
+        // Once the sequence runs out, start over from the initial id and
+        // look for a gap, giving up when the scan is back where it started.
+        let start = self.next_id.take().unwrap_or_else(Id::initial);
+        let mut idx = start.clone();
+        while self.index.contains_key(&idx) {
+            idx = idx.successor().unwrap_or_else(Id::initial);
+            if idx == start {
+                return None;
+            }
+        }
+        self.next_id = idx.successor();
+        Some(idx)
+    }
+}
+
+impl<'a, T, W: Weight, E, Id: NodeId> IntoIterator for &'a Graph<T, W, E, Id> {
+    type Item = &'a Node<T, W, E, Id>;
+    type IntoIter = std::slice::Iter<'a, Node<T, W, E, Id>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.nodes()
+    }
+}
+
+/// Collects `(from, to, weight)` triples into a directed graph; see
+/// [`Graph::from_edges`].
+///
+/// # Panics
+/// Panics if an edge is rejected, for example because of an invalid weight.
+impl<T, W: Weight, E: Default + Clone, Id: NodeId> FromIterator<(Id, Id, W)>
+    for Graph<T, W, E, Id>
+{
+    fn from_iter<I: IntoIterator<Item = (Id, Id, W)>>(iter: I) -> Self {
+        Self::from_edges(iter).unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+/// Inserts `(from, to, weight)` triples; see [`Graph::insert_edges`].
+///
+/// # Panics
+/// Panics if an edge or a new endpoint node is rejected.
+impl<T, W: Weight, E: Default + Clone, Id: NodeId> Extend<(Id, Id, W)> for Graph<T, W, E, Id> {
+    fn extend<I: IntoIterator<Item = (Id, Id, W)>>(&mut self, iter: I) {
+        self.insert_edges(iter)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+}
+
+/// The id of an inserted edge and the edge it replaced, if any.
+type Connected<W, E, Id> = (EdgeId, Option<Edge<W, E, Id>>);
+
+/// A removed edge along with the neighbor it pointed to.
+type Removed<W, E, Id> = (Id, Edge<W, E, Id>);
+
+/// Mutable references to two distinct nodes of a [`Graph`].
+type NodePair<'a, T, W, E, Id> = (&'a mut Node<T, W, E, Id>, &'a mut Node<T, W, E, Id>);
+
+/// A boxed observer, as stored by a [`Graph`].
+type BoxedObserver<T, W, E, Id> = Box<dyn GraphObserver<T, W, E, Id> + Send + Sync>;
+
+/// A boxed weight check, as stored by a [`Graph`] or a
+/// [`GraphBuilder`](builder::GraphBuilder).
+pub(crate) type WeightValidator<W> = Box<dyn Fn(&W) -> bool + Send + Sync>;
+
+/// A mutable handle to an edge in a [`Graph`], returned by
+/// [`Graph::get_edge_mut`].
+/// It dereferences to the [`Edge`]. In an undirected graph, the weight and
+/// payload are copied to the mirrored edge when the handle is dropped.
+pub struct EdgeMut<'a, W: Clone, E: Clone, Id = u32> {
+    edge: &'a mut Edge<W, E, Id>,
+    mirror: Option<&'a mut Edge<W, E, Id>>,
+}
+
+impl<W: Clone, E: Clone, Id> Deref for EdgeMut<'_, W, E, Id> {
+    type Target = Edge<W, E, Id>;
+
+    fn deref(&self) -> &Self::Target {
+        self.edge
+    }
+}
+
+impl<W: Clone, E: Clone, Id> DerefMut for EdgeMut<'_, W, E, Id> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.edge
     }
-    fn get_node_mut(&mut self, idx: u32) -> Option<&mut Node<T>> {
-        let pos = self.nodes.iter().position(|n| n.idx == idx).unwrap();
-        self.nodes.get_mut(pos)
+}
+
+impl<W: Clone, E: Clone, Id> Drop for EdgeMut<'_, W, E, Id> {
+    fn drop(&mut self) {
+        if let Some(mirror) = self.mirror.as_mut() {
+            mirror.weight = self.edge.weight.clone();
+            mirror.data = self.edge.data.clone();
+        }
     }
 }
 
@@ -191,6 +1482,7 @@ mod tests {
     use super::*;
 
     #[test]
+    #[allow(unused_variables)]
     fn test_creating_nodes_and_edges() {
         let mut node_10 = Node::<()>::new(10);
         let node_20 = Node::<()>::new(20);
@@ -222,6 +1514,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(unused_variables, unused_mut)]
     fn test_creating_graph_objects_with_labelled_nodes() {
         let mut graph: Graph<String> = Graph::new(5, false);
         assert_eq!(graph.capacity, 5);
@@ -285,7 +1578,7 @@ mod tests {
         assert!(graph.insert_node(node_30).is_ok());
         assert!(matches!(
             graph.insert_node(node_40).unwrap_err(),
-            GraphError::CapacityExceeded
+            GraphError::CapacityExceeded { capacity: 2 }
         ));
 
         assert!(graph.has_node(20));
@@ -326,7 +1619,14 @@ mod tests {
         let _ = graph.insert_edge(40, 20, 1012.10);
         let _ = graph.insert_edge(40, 30, 99.0);
 
-        assert!(graph.remove_edge(40, 2024).is_none());
+        assert_eq!(
+            graph.remove_edge(40, 2024).unwrap_err(),
+            GraphError::MissingEdge { from: 40, to: 2024 }
+        );
+        assert_eq!(
+            graph.remove_edge(40, 50).unwrap_err(),
+            GraphError::MissingTargetNode { idx: 50 }
+        );
         let result = graph.remove_edge(40, 20).unwrap();
 
         assert_eq!(result.0, 20);
@@ -334,4 +1634,1239 @@ mod tests {
         assert_eq!(result.1.to_node, 20);
         assert_eq!(result.1.weight, 1012.10);
     }
+
+    #[test]
+    fn test_node_lookup_in_large_graph() {
+        let mut graph: Graph<()> = Graph::new(10_000, true);
+        for idx in (0..10_000).rev() {
+            assert!(graph.insert_node(Node::new(idx)).is_ok());
+        }
+        assert!(graph.insert_edge(0, 9_999, 1.5).unwrap().is_none());
+        assert!(graph.has_node(5_000));
+        assert!(!graph.has_node(10_000));
+        assert_eq!(graph.get_edge(0, 9_999).unwrap().weight, 1.5);
+        assert!(graph.remove_edge(0, 9_999).is_ok());
+        assert!(!graph.has_edge(0, 9_999));
+    }
+
+    #[test]
+    fn test_node_removal() {
+        let mut graph: Graph<String> = Graph::new(5, false);
+
+        let node_20: Node<String> = Node::with_label(20, String::from("Furniture"));
+        let node_30: Node<String> = Node::with_label(30, String::from("Laptop"));
+        let node_40: Node<String> = Node::with_label(40, String::from("Clock"));
+
+        let _ = graph.insert_node(node_20);
+        let _ = graph.insert_node(node_30);
+        let _ = graph.insert_node(node_40);
+
+        let _ = graph.insert_edge(40, 20, 1012.10);
+        let _ = graph.insert_edge(30, 20, 99.0);
+        let _ = graph.insert_edge(20, 40, 7.0);
+
+        let removed = graph.remove_node(20).unwrap();
+        assert_eq!(removed.idx, 20);
+        assert_eq!(removed.number_of_edges(), 1);
+        assert_eq!(removed.label.unwrap(), String::from("Furniture"));
+
+        assert_eq!(graph.len(), 2);
+        assert!(!graph.has_node(20));
+        assert!(!graph.is_edge(40, 20));
+        assert!(!graph.is_edge(30, 20));
+        assert!(graph.has_node(30));
+        assert!(graph.has_node(40));
+
+        assert!(matches!(
+            graph.remove_node(20),
+            Err(GraphError::MissingNode { idx: 20 })
+        ));
+    }
+
+    #[test]
+    fn test_undirected_edges_are_mirrored() {
+        let mut graph: Graph<()> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(20));
+        let _ = graph.insert_node(Node::new(30));
+
+        assert!(graph.insert_edge(20, 30, 5.0).unwrap().is_none());
+        let forward = graph.get_edge(20, 30).unwrap();
+        let backward = graph.get_edge(30, 20).unwrap();
+        assert_eq!((forward.from_node, forward.to_node), (20, 30));
+        assert_eq!((backward.from_node, backward.to_node), (30, 20));
+        assert_eq!(forward.weight, backward.weight);
+
+        // Updating from either side keeps both directions in sync.
+        let old = graph.insert_edge(30, 20, 8.0).unwrap().unwrap();
+        assert_eq!(old.weight, 5.0);
+        assert_eq!(graph.get_edge(20, 30).unwrap().weight, 8.0);
+        assert_eq!(graph.get_edge(30, 20).unwrap().weight, 8.0);
+
+        let (neighbor, removed) = graph.remove_edge(20, 30).unwrap();
+        assert_eq!(neighbor, 30);
+        assert_eq!(removed.weight, 8.0);
+        assert!(!graph.is_edge(20, 30));
+        assert!(!graph.is_edge(30, 20));
+    }
+
+    #[test]
+    fn test_inserting_edge_with_missing_endpoint() {
+        let mut graph: Graph<()> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(20));
+
+        assert!(matches!(
+            graph.insert_edge(10, 20, 1.0),
+            Err(GraphError::MissingSourceNode { idx: 10 })
+        ));
+        assert!(matches!(
+            graph.insert_edge(20, 30, 1.0),
+            Err(GraphError::MissingTargetNode { idx: 30 })
+        ));
+        assert_eq!(graph.nodes[0].number_of_edges(), 0);
+    }
+
+    #[test]
+    fn test_validating_graph() {
+        let mut graph: Graph<()> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(20));
+        let _ = graph.insert_node(Node::new(30));
+        let _ = graph.insert_edge(20, 30, 1.0);
+        assert!(graph.validate().is_valid());
+
+        // Corrupt the adjacency lists behind the graph's back.
+        graph.nodes[0].add_edge(99, 1.0);
+        graph.nodes[1].edges.remove(&20);
+        graph.nodes[1]
+            .edges
+            .insert(30, vec![Edge::new(20, 30, 2.0)]);
+
+        let report = graph.validate();
+        assert!(!report.is_valid());
+        assert_eq!(report.dangling_edges, vec![(20, 99)]);
+        assert_eq!(report.mismatched_edges, vec![(30, 20)]);
+        assert_eq!(report.asymmetric_edges, vec![(20, 30)]);
+    }
+
+    #[test]
+    fn test_graph_errors() {
+        let mut graph: Graph<()> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(20));
+        let _ = graph.insert_node(Node::new(30));
+
+        let err = graph.insert_edge(20, 30, f32::NAN).unwrap_err();
+        assert_eq!(err, GraphError::InvalidWeight { from: 20, to: 30 });
+        assert!(!graph.is_edge(20, 30));
+
+        assert_eq!(
+            graph.get_node_mut(40).err(),
+            Some(GraphError::MissingNode { idx: 40 })
+        );
+
+        // Usable as a boxed `std::error::Error` with `?`.
+        fn remove(graph: &mut Graph<()>) -> Result<(), Box<dyn std::error::Error>> {
+            graph.remove_edge(20, 30)?;
+            Ok(())
+        }
+        let err = remove(&mut graph).unwrap_err();
+        assert_eq!(err.to_string(), "no edge exists from node 20 to node 30");
+    }
+
+    #[test]
+    fn test_inserting_duplicate_node() {
+        let mut graph: Graph<String> = Graph::new(5, false);
+
+        let node_20: Node<String> = Node::with_label(20, String::from("Furniture"));
+        let duplicate: Node<String> = Node::with_label(20, String::from("Laptop"));
+
+        assert!(graph.insert_node(node_20).is_ok());
+        assert!(matches!(
+            graph.insert_node(duplicate),
+            Err(GraphError::DuplicateNode { idx: 20 })
+        ));
+        assert_eq!(graph.len(), 1);
+
+        let mut merged: Node<String> = Node::with_label(20, String::from("Laptop"));
+        merged.add_edge(20, 1.0);
+        let keep_existing = |old: Option<String>, new: Option<String>| old.or(new);
+        assert!(graph.insert_or_merge_node(merged, keep_existing).is_ok());
+        assert_eq!(graph.len(), 1);
+        assert_eq!(graph.nodes[0].label.as_deref(), Some("Furniture"));
+        assert!(graph.is_edge(20, 20));
+    }
+
+    #[test]
+    fn test_integer_edge_weights() {
+        let mut graph: Graph<&str, u64> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::with_label(1, "Oslo"));
+        let _ = graph.insert_node(Node::with_label(2, "Bergen"));
+        let _ = graph.insert_node(Node::with_label(3, "Trondheim"));
+
+        let _ = graph.insert_edge(1, 2, 463);
+        let _ = graph.insert_edge(2, 3, 701);
+
+        let cost = graph.get_edge(1, 2).unwrap().weight + graph.get_edge(3, 2).unwrap().weight;
+        assert_eq!(cost, 1164);
+        assert!(graph.validate().is_valid());
+    }
+
+    #[test]
+    fn test_edge_payloads() {
+        #[derive(Clone, Debug, Default, PartialEq)]
+        struct Flight {
+            code: &'static str,
+            seats: u32,
+        }
+
+        let mut graph: Graph<(), f32, Flight> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let _ = graph.insert_node(Node::new(3));
+
+        let flight = Flight {
+            code: "SK4035",
+            seats: 180,
+        };
+        assert!(graph.insert_edge_with_data(1, 2, 55.0, flight).is_ok());
+        assert!(graph.insert_edge(2, 3, 40.0).is_ok());
+
+        assert_eq!(graph.get_edge(1, 2).unwrap().data().code, "SK4035");
+        assert_eq!(graph.get_edge(2, 3).unwrap().data(), &Flight::default());
+
+        graph.get_edge_mut(2, 1).unwrap().data_mut().seats -= 1;
+        assert_eq!(graph.get_edge(1, 2).unwrap().data().seats, 179);
+        assert_eq!(graph.get_edge(2, 1).unwrap().data().seats, 179);
+        assert!(graph.get_edge_mut(1, 3).is_none());
+    }
+
+    #[test]
+    fn test_parallel_edges_in_multigraph() {
+        let mut graph: Graph<&str> = Graph::new_multigraph(5, true);
+        let _ = graph.insert_node(Node::with_label(1, "OSL"));
+        let _ = graph.insert_node(Node::with_label(2, "CPH"));
+
+        let first = graph.add_edge(1, 2, 70.0).unwrap();
+        assert!(graph.insert_edge(1, 2, 75.0).unwrap().is_none());
+        let third = graph.add_edge(2, 1, 80.0).unwrap();
+        assert_ne!(first, third);
+
+        assert_eq!(graph.edges_between(1, 2).count(), 3);
+        assert_eq!(graph.edges_between(2, 1).count(), 3);
+        assert_eq!(graph.nodes[0].number_of_edges(), 3);
+        assert_eq!(graph.get_edge_by_id(1, 2, third).unwrap().weight, 80.0);
+        assert_eq!(graph.get_edge_by_id(2, 1, third).unwrap().to_node, 1);
+
+        let removed = graph.remove_edge_by_id(1, 2, third).unwrap();
+        assert_eq!(removed.weight, 80.0);
+        assert!(graph.get_edge_by_id(2, 1, third).is_none());
+        assert_eq!(graph.edges_between(2, 1).count(), 2);
+
+        // Removing by endpoints takes the oldest edge first.
+        let (_, oldest) = graph.remove_edge(2, 1).unwrap();
+        assert_eq!(oldest.id(), first);
+        let remaining: Vec<f32> = graph.edges_between(1, 2).map(|e| e.weight).collect();
+        assert_eq!(remaining, vec![75.0]);
+        assert!(graph.validate().is_valid());
+
+        assert!(matches!(
+            graph.remove_edge_by_id(1, 2, first),
+            Err(GraphError::MissingEdge { from: 1, to: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_simple_graph_keeps_edge_id_on_update() {
+        let mut graph: Graph<()> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+
+        let id = graph.add_edge(1, 2, 1.0).unwrap();
+        assert_eq!(graph.add_edge(1, 2, 2.0).unwrap(), id);
+        assert_eq!(graph.edges_between(1, 2).count(), 1);
+        assert_eq!(graph.get_edge(1, 2).unwrap().weight, 2.0);
+    }
+
+    #[test]
+    fn test_self_loop_policies() {
+        let mut graph: Graph<()> = Graph::new(5, false).with_self_loops(SelfLoops::Ignore);
+        let _ = graph.insert_node(Node::new(1));
+        assert!(graph.insert_edge(1, 1, 1.0).unwrap().is_none());
+        assert!(!graph.is_edge(1, 1));
+        assert!(matches!(
+            graph.add_edge(1, 1, 1.0),
+            Err(GraphError::SelfLoop { idx: 1 })
+        ));
+
+        let mut graph: Graph<()> = Graph::new(5, false).with_self_loops(SelfLoops::Reject);
+        let _ = graph.insert_node(Node::new(1));
+        assert!(matches!(
+            graph.insert_edge(1, 1, 1.0),
+            Err(GraphError::SelfLoop { idx: 1 })
+        ));
+        assert!(!graph.is_edge(1, 1));
+        let mut node = Node::new(2);
+        node.add_edge(2, 1.0);
+        assert_eq!(
+            graph.insert_node(node),
+            Err(GraphError::SelfLoop { idx: 2 })
+        );
+        assert!(!graph.has_node(2));
+
+        let mut graph: Graph<()> = Graph::new(5, false).with_self_loops(SelfLoops::Ignore);
+        let mut node = Node::new(2);
+        node.add_edge(2, 1.0);
+        assert_eq!(graph.insert_node(node), Ok(()));
+        assert!(!graph.is_edge(2, 2));
+        assert_eq!(graph.in_degree(2), Some(0));
+    }
+
+    #[test]
+    fn test_degree_with_self_loops() {
+        for undirected in [false, true] {
+            let mut graph: Graph<()> = Graph::new(5, undirected);
+            let _ = graph.insert_node(Node::new(1));
+            let _ = graph.insert_node(Node::new(2));
+            let _ = graph.insert_edge(1, 1, 1.0);
+            let _ = graph.insert_edge(1, 2, 1.0);
+
+            assert_eq!(graph.degree(1), Some(3));
+            assert_eq!(graph.degree(2), Some(1));
+            assert_eq!(graph.degree(3), None);
+            assert!(graph.validate().is_valid());
+        }
+
+        let mut graph: Graph<()> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_edge(1, 1, 1.0);
+        assert_eq!(graph.out_degree(1), Some(1));
+    }
+
+    #[test]
+    fn test_string_node_ids() {
+        let mut graph: Graph<(), f32, (), String> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(String::from("alice")));
+        let _ = graph.insert_node(Node::new(String::from("bob")));
+
+        assert!(graph
+            .insert_edge(String::from("alice"), String::from("bob"), 1.0)
+            .is_ok());
+        assert!(graph.is_edge(String::from("bob"), String::from("alice")));
+
+        let err = graph
+            .insert_edge(String::from("alice"), String::from("carol"), 1.0)
+            .unwrap_err();
+        assert_eq!(
+            err,
+            GraphError::MissingTargetNode {
+                idx: String::from("carol")
+            }
+        );
+        assert_eq!(err.to_string(), "target node \"carol\" does not exist");
+
+        let removed = graph.remove_node(String::from("bob")).unwrap();
+        assert_eq!(removed.idx, "bob");
+        assert_eq!(graph.out_degree(String::from("alice")), Some(0));
+    }
+
+    #[test]
+    fn test_allocating_node_ids() {
+        let mut graph: Graph<&str> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::with_label(1, "explicit"));
+
+        let first = graph.add_node("first").unwrap();
+        let second = graph.add_node("second").unwrap();
+        assert_eq!((first, second), (0, 2));
+        assert_eq!(graph.get_node(2).unwrap().label, Some("second"));
+
+        // Without reuse, freed ids are not handed out again.
+        graph.remove_node(first).unwrap();
+        assert_eq!(graph.add_node("third").unwrap(), 3);
+
+        let mut graph: Graph<&str, f32, (), u8> = Graph::new(300, false).with_id_reuse(true);
+        for _ in 0..=u8::MAX {
+            graph.add_node("node").unwrap();
+        }
+        assert_eq!(graph.add_node("full"), Err(GraphError::IdsExhausted));
+        graph.remove_node(7).unwrap();
+        graph.remove_node(3).unwrap();
+        assert_eq!(graph.add_node("reused").unwrap(), 3);
+        assert_eq!(graph.add_node("reused").unwrap(), 7);
+
+        // Without reuse, the sequence wraps around to find a gap once it
+        // reaches the largest id.
+        let mut graph: Graph<&str, f32, (), u8> = Graph::new(300, false);
+        for _ in 0..250 {
+            graph.add_node("node").unwrap();
+        }
+        for idx in 250..=u8::MAX {
+            graph.insert_node(Node::new(idx)).unwrap();
+        }
+        graph.remove_node(3).unwrap();
+        assert_eq!(graph.add_node("wrapped"), Ok(3));
+        assert_eq!(graph.add_node("full"), Err(GraphError::IdsExhausted));
+    }
+
+    #[test]
+    fn test_growth_policies() {
+        let mut graph: Graph<()> = Graph::new(2, false).with_growth_policy(GrowthPolicy::Doubling);
+        for idx in 0..5 {
+            assert!(graph.insert_node(Node::new(idx)).is_ok());
+        }
+        assert_eq!(graph.capacity, 8);
+
+        graph.shrink_to_fit();
+        assert_eq!(graph.capacity, 5);
+        graph.reserve(10);
+        assert_eq!(graph.capacity, 15);
+
+        let crossings = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = crossings.clone();
+        let policy = GrowthPolicy::Unbounded {
+            soft_limit: 3,
+            on_soft_limit: Box::new(move |len| seen.lock().unwrap().push(len)),
+        };
+        fn assert_send_sync<T: Send + Sync>(_: &T) {}
+        assert_send_sync(&policy);
+        let mut graph: Graph<()> = Graph::new(0, false).with_growth_policy(policy);
+        for idx in 0..6 {
+            assert!(graph.insert_node(Node::new(idx)).is_ok());
+        }
+        assert!(graph.capacity >= graph.len());
+        assert_eq!(*crossings.lock().unwrap(), vec![4]);
+
+        let mut graph: Graph<()> = Graph::new(1, false);
+        let _ = graph.insert_node(Node::new(1));
+        graph.reserve(1);
+        assert_eq!(graph.capacity, 2);
+        assert!(graph.insert_node(Node::new(2)).is_ok());
+        assert!(matches!(
+            graph.insert_node(Node::new(3)),
+            Err(GraphError::CapacityExceeded { capacity: 2 })
+        ));
+    }
+
+    #[test]
+    fn test_stale_node_handles() {
+        let mut graph: Graph<&str> = Graph::new(5, false).with_id_reuse(true);
+        let a = graph.add_node("a").unwrap();
+        let b = graph.add_node("b").unwrap();
+        let handle_a = graph.handle(a).unwrap();
+        let handle_b = graph.handle(b).unwrap();
+        assert!(graph.handle(10).is_none());
+
+        graph.get_node_mut_by_handle(&handle_b).unwrap().label = Some("B");
+        assert_eq!(
+            graph.get_node_by_handle(&handle_b).unwrap().label,
+            Some("B")
+        );
+
+        graph.remove_node_by_handle(&handle_a).unwrap();
+        assert!(!graph.contains_handle(&handle_a));
+        assert!(matches!(
+            graph.remove_node_by_handle(&handle_a),
+            Err(GraphError::StaleHandle { idx: 0 })
+        ));
+
+        // The id is reused, but the old handle keeps pointing nowhere.
+        assert_eq!(graph.add_node("c").unwrap(), a);
+        let handle_c = graph.handle(a).unwrap();
+        assert_ne!(handle_a, handle_c);
+        assert!(graph.get_node_by_handle(&handle_a).is_err());
+        assert_eq!(
+            graph.get_node_by_handle(&handle_c).unwrap().label,
+            Some("c")
+        );
+        assert!(graph.contains_handle(&handle_b));
+
+        // Removed ids leave nothing behind, so churning through fresh ids
+        // doesn't grow the graph.
+        let mut graph: Graph<(), f32, (), String> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(String::from("a")));
+        let first = graph.handle(String::from("a")).unwrap();
+        for round in 0..100 {
+            let idx = round.to_string();
+            let _ = graph.insert_node(Node::new(idx.clone()));
+            let _ = graph.remove_node(idx);
+        }
+        let _ = graph.remove_node(String::from("a"));
+        let _ = graph.insert_node(Node::new(String::from("a")));
+        assert_eq!(graph.generations.len(), 1);
+        assert!(!graph.contains_handle(&first));
+    }
+
+    #[test]
+    fn test_reverse_adjacency() {
+        let mut graph: Graph<()> = Graph::new_multigraph(5, false);
+        for idx in 1..=4 {
+            let _ = graph.insert_node(Node::new(idx));
+        }
+        let _ = graph.insert_edge(1, 3, 1.0);
+        let _ = graph.insert_edge(2, 3, 2.0);
+        let parallel = graph.add_edge(2, 3, 3.0).unwrap();
+        let _ = graph.insert_edge(3, 3, 4.0);
+        let _ = graph.insert_edge(3, 4, 5.0);
+
+        let mut preds: Vec<u32> = graph.predecessors(3).copied().collect();
+        preds.sort();
+        assert_eq!(preds, vec![1, 2, 3]);
+        assert_eq!(graph.in_degree(3), Some(4));
+        assert_eq!(graph.degree(3), Some(6));
+        let mut weights: Vec<f32> = graph.incoming_edges(3).map(|e| e.weight).collect();
+        weights.sort_by(f32::total_cmp);
+        assert_eq!(weights, vec![1.0, 2.0, 3.0, 4.0]);
+
+        graph.remove_edge_by_id(2, 3, parallel).unwrap();
+        assert_eq!(graph.in_degree(3), Some(3));
+        graph.remove_edge(2, 3).unwrap();
+        assert_eq!(graph.predecessors(3).count(), 2);
+
+        graph.remove_node(3).unwrap();
+        assert_eq!(graph.in_degree(4), Some(0));
+        assert_eq!(graph.out_degree(1), Some(0));
+        assert_eq!(graph.in_degree(3), None);
+        assert_eq!(graph.predecessors(3).count(), 0);
+    }
+
+    #[test]
+    fn test_reverse_adjacency_of_inserted_and_merged_nodes() {
+        let mut graph: Graph<()> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let _ = graph.insert_edge(1, 2, 1.0);
+        assert_eq!(graph.predecessors(1).collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(graph.in_degree(2), Some(1));
+
+        let mut node = Node::new(3);
+        node.add_edge(1, 1.0);
+        let _ = graph.insert_node(node);
+        let mut preds: Vec<u32> = graph.predecessors(1).copied().collect();
+        preds.sort();
+        assert_eq!(preds, vec![2, 3]);
+        assert_eq!(graph.predecessors(3).collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(graph.in_degree(1), Some(2));
+        assert_eq!(graph.in_degree(3), Some(1));
+        assert!(graph.validate().is_valid());
+
+        let mut node = Node::new(3);
+        node.add_edge(2, 1.0);
+        let _ = graph.insert_or_merge_node(node, |old, _| old);
+        assert_eq!(graph.in_degree(1), Some(2));
+        assert_eq!(graph.in_degree(2), Some(2));
+        assert_eq!(graph.in_degree(3), Some(2));
+        assert!(graph.validate().is_valid());
+
+        graph.remove_edge(1, 2).unwrap();
+        assert_eq!(graph.in_degree(1), Some(1));
+        assert_eq!(graph.in_degree(2), Some(1));
+    }
+
+    #[test]
+    fn test_iterators() {
+        let mut graph: Graph<&str> = Graph::new(5, true);
+        for (idx, label) in [(1, "a"), (2, "b"), (3, "c")] {
+            let _ = graph.insert_node(Node::with_label(idx, label));
+        }
+        let _ = graph.insert_edge(1, 2, 1.0);
+        let _ = graph.insert_edge(3, 1, 2.0);
+        let _ = graph.insert_edge(2, 2, 3.0);
+
+        let mut edges: Vec<(u32, u32, f32)> = graph
+            .edges()
+            .map(|(from, to, w)| (*from, *to, *w))
+            .collect();
+        edges.sort_by(|a, b| a.2.total_cmp(&b.2));
+        assert_eq!(edges, vec![(1, 2, 1.0), (1, 3, 2.0), (2, 2, 3.0)]);
+
+        let mut neighbors: Vec<u32> = graph.neighbors(1).copied().collect();
+        neighbors.sort();
+        assert_eq!(neighbors, vec![2, 3]);
+        assert_eq!(graph.neighbors(9).count(), 0);
+
+        for node in graph.neighbors_mut(1) {
+            node.label = Some("neighbor");
+        }
+        let labels: Vec<_> = graph.nodes().map(|node| node.label.unwrap()).collect();
+        assert_eq!(labels, vec!["a", "neighbor", "neighbor"]);
+
+        for node in graph.nodes_mut() {
+            node.label = Some("x");
+        }
+        assert_eq!((&graph).into_iter().count(), 3);
+        for node in &graph {
+            assert_eq!(node.label, Some("x"));
+        }
+
+        let mut directed: Graph<()> = Graph::new(5, false);
+        let _ = directed.insert_node(Node::new(1));
+        let _ = directed.insert_node(Node::new(2));
+        let _ = directed.insert_edge(1, 2, 1.0);
+        let _ = directed.insert_edge(2, 1, 1.0);
+        assert_eq!(directed.edges().count(), 2);
+    }
+
+    #[test]
+    fn test_updating_edge_weights() {
+        let mut graph: Graph<(), u64> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let _ = graph.insert_edge(1, 2, 10);
+
+        assert_eq!(graph.update_weight(2, 1, 15), Ok(10));
+        let edge = graph.get_edge(1, 2).unwrap();
+        assert_eq!(
+            (*edge.from_node(), *edge.to_node(), *edge.weight()),
+            (1, 2, 15)
+        );
+        assert_eq!(edge.clone().reversed(), *graph.get_edge(2, 1).unwrap());
+
+        *graph.get_edge_mut(1, 2).unwrap().weight_mut() += 5;
+        assert_eq!(*graph.get_edge(2, 1).unwrap().weight(), 20);
+        assert_eq!(
+            graph.update_weight(1, 1, 0),
+            Err(GraphError::MissingEdge { from: 1, to: 1 })
+        );
+        assert_eq!(
+            graph.update_weight(1, 3, 0),
+            Err(GraphError::MissingTargetNode { idx: 3 })
+        );
+
+        let mut floats: Graph<()> = Graph::new(5, false);
+        let _ = floats.insert_node(Node::new(1));
+        let _ = floats.insert_edge(1, 1, 1.0);
+        assert_eq!(
+            floats.update_weight(1, 1, f32::NAN),
+            Err(GraphError::InvalidWeight { from: 1, to: 1 })
+        );
+    }
+
+    #[test]
+    fn test_mutation_observer() {
+        use std::sync::{Arc, Mutex};
+
+        struct Log(Arc<Mutex<Vec<String>>>);
+        impl GraphObserver<()> for Log {
+            fn on_node_inserted(&mut self, node: &Node<()>) {
+                self.0.lock().unwrap().push(format!("+node {}", node.idx));
+            }
+            fn on_node_removed(&mut self, node: &Node<()>) {
+                self.0.lock().unwrap().push(format!("-node {}", node.idx));
+            }
+            fn on_edge_inserted(&mut self, edge: &Edge) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("+edge {}->{}", edge.from_node, edge.to_node));
+            }
+            fn on_edge_updated(&mut self, edge: &Edge) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("~edge {}", edge.weight));
+            }
+            fn on_edge_removed(&mut self, edge: &Edge) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("-edge {}->{}", edge.from_node, edge.to_node));
+            }
+        }
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph: Graph<()> = Graph::new(5, true);
+        graph.add_observer(Log(Arc::clone(&log)));
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let _ = graph.insert_edge(1, 2, 1.0);
+        let _ = graph.insert_edge(2, 1, 2.0);
+        let _ = graph.update_weight(1, 2, 3.0);
+        let mut node = Node::new(1);
+        node.add_edge(2, 4.0);
+        let _ = graph.insert_or_merge_node(node, |old, _| old);
+        let _ = graph.insert_edge(1, 3, 1.0);
+        let _ = graph.remove_edge(2, 1);
+        let _ = graph.remove_node(2);
+        assert_eq!(
+            *log.lock().unwrap(),
+            vec![
+                "+node 1",
+                "+node 2",
+                "+edge 1->2",
+                "~edge 2",
+                "~edge 3",
+                "~edge 4",
+                "-edge 2->1",
+                "-node 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bulk_construction() {
+        let mut graph: Graph<()> = (0..1_000).map(|i| (i, (i + 1) % 1_000, 1.0)).collect();
+        assert_eq!(graph.len(), 1_000);
+        assert_eq!(graph.edges().count(), 1_000);
+        assert_eq!(graph.in_degree(0), Some(1));
+        // The bulk path reserves once, while inserting one by one keeps
+        // doubling the capacity.
+        assert_eq!(graph.capacity, 1_000);
+        let mut one_by_one: Graph<()> =
+            Graph::new(0, false).with_growth_policy(GrowthPolicy::Doubling);
+        for i in 0..1_000 {
+            for idx in [i, (i + 1) % 1_000] {
+                if one_by_one.node(&idx).is_none() {
+                    let _ = one_by_one.insert_node(Node::new(idx));
+                }
+            }
+            let _ = one_by_one.insert_edge(i, (i + 1) % 1_000, 1.0);
+        }
+        assert_eq!(one_by_one.capacity, 1_024);
+
+        graph.extend([(0, 5_000, 2.0), (5_000, 5_001, 3.0)]);
+        assert_eq!(graph.len(), 1_002);
+        assert!(graph.is_edge(5_000, 5_001));
+
+        let mut fixed: Graph<()> = Graph::new(2, true);
+        assert_eq!(
+            fixed.insert_edges([(1, 2, 1.0), (2, 3, 1.0)]),
+            Err(GraphError::CapacityExceeded { capacity: 2 })
+        );
+        assert!(fixed.is_edge(2, 1));
+        assert_eq!(
+            Graph::<()>::from_edges([(1, 2, f32::NAN)]).err(),
+            Some(GraphError::InvalidWeight { from: 1, to: 2 })
+        );
+    }
+
+    #[test]
+    fn test_graph_builder() {
+        use builder::GraphBuilder;
+
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Graph<()>>();
+        assert_send_sync::<GraphBuilder<()>>();
+
+        let graph = GraphBuilder::<(), u32>::new()
+            .multigraph(true)
+            .capacity(1)
+            .nodes([Node::new(1), Node::new(2), Node::new(3)])
+            .edge(1, 2, 5)
+            .edge(1, 2, 7)
+            .build()
+            .unwrap();
+        assert!(graph.is_multigraph() && !graph.undirected);
+        assert_eq!(graph.edges_between(1, 2).count(), 2);
+        assert_eq!(graph.len(), 3);
+
+        let rejected = |builder: GraphBuilder<()>| builder.build().err();
+        let base = || GraphBuilder::new().nodes([Node::new(1), Node::new(2)]);
+        assert_eq!(
+            rejected(base().node(Node::new(1))),
+            Some(GraphError::DuplicateNode { idx: 1 })
+        );
+        assert_eq!(
+            rejected(base().edge(1, 3, 1.0)),
+            Some(GraphError::MissingTargetNode { idx: 3 })
+        );
+        assert_eq!(
+            rejected(base().self_loops(SelfLoops::Reject).edge(2, 2, 1.0)),
+            Some(GraphError::SelfLoop { idx: 2 })
+        );
+        assert_eq!(
+            rejected(base().weight_validator(|w| *w < 10.0).edge(1, 2, 10.0)),
+            Some(GraphError::InvalidWeight { from: 1, to: 2 })
+        );
+        assert_eq!(
+            rejected(base().growth_policy(GrowthPolicy::Fixed).capacity(1)),
+            Some(GraphError::CapacityExceeded { capacity: 1 })
+        );
+    }
+
+    #[test]
+    fn test_label_index() {
+        let mut graph: Graph<String> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::with_label(1, String::from("Clock")));
+        graph = graph.with_label_index();
+        assert!(graph.has_label_index());
+        let _ = graph.insert_node(Node::with_label(2, String::from("Laptop")));
+        let _ = graph.insert_node(Node::with_label(3, String::from("Clock")));
+        let clock = String::from("Clock");
+        let laptop = String::from("Laptop");
+        let mut clocks: Vec<_> = graph.find_by_label(&clock).collect();
+        clocks.sort();
+        assert_eq!(clocks, vec![&1, &3]);
+
+        assert_eq!(graph.set_label(1, laptop.clone()), Ok(Some(clock.clone())));
+        assert_eq!(graph.find_by_label(&clock).collect::<Vec<_>>(), vec![&3]);
+        assert_eq!(graph.find_by_label(&laptop).count(), 2);
+
+        let _ = graph.insert_or_merge_node(Node::with_label(2, clock.clone()), |_, new| new);
+        assert_eq!(graph.find_by_label(&clock).count(), 2);
+        let _ = graph.remove_node(3);
+        assert_eq!(graph.find_by_label(&clock).collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(
+            graph.set_label(3, clock),
+            Err(GraphError::MissingNode { idx: 3 })
+        );
+
+        let unindexed: Graph<&str> = Graph::from_iter([(1, 2, 1.0)]);
+        assert_eq!(unindexed.find_by_label(&"x").count(), 0);
+    }
+
+    #[test]
+    fn test_set_label_notifies_observers() {
+        use std::sync::{Arc, Mutex};
+
+        struct Labels(Arc<Mutex<Vec<Option<&'static str>>>>);
+        impl GraphObserver<&'static str> for Labels {
+            fn on_node_updated(&mut self, node: &Node<&'static str>) {
+                self.0.lock().unwrap().push(node.label);
+            }
+        }
+
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let mut graph: Graph<&'static str> = Graph::new(5, false).with_label_index();
+        graph.add_observer(Labels(Arc::clone(&log)));
+        let _ = graph.insert_node(Node::with_label(1, "a"));
+        let _ = graph.insert_node(Node::with_label(2, "a"));
+        assert_eq!(graph.set_label(1, "b"), Ok(Some("a")));
+        assert_eq!(graph.set_label(1, "c"), Ok(Some("b")));
+        assert_eq!(*log.lock().unwrap(), vec![Some("b"), Some("c")]);
+        assert_eq!(graph.find_by_label(&"a").collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(graph.find_by_label(&"b").count(), 0);
+    }
+
+    #[test]
+    fn test_property_graph() {
+        use property::{Properties, PropertyGraph, PropertyValue};
+
+        let mut graph: PropertyGraph = PropertyGraph::new(5, true);
+        let person = |name: &str| Properties::labelled("Person").with("name", name);
+        let _ = graph.insert_node(Node::with_label(1, person("Ada")));
+        let _ = graph.insert_node(Node::with_label(2, person("Alan")));
+        let _ = graph.insert_node(Node::new(3));
+        let knows = Properties::labelled("KNOWS").with("tags", vec!["math", "code"]);
+        let _ = graph.insert_edge_with_data(1, 2, 1.0, knows);
+
+        assert_eq!(graph.set_node_property(3, "active", true), Ok(None));
+        assert_eq!(
+            graph.set_node_property(1, "name", "Ada Lovelace"),
+            Ok(Some(PropertyValue::from("Ada")))
+        );
+        assert_eq!(
+            graph
+                .node_property(1, "name")
+                .and_then(PropertyValue::as_str),
+            Some("Ada Lovelace")
+        );
+        assert_eq!(
+            graph.remove_node_property(2, "name"),
+            Ok(Some("Alan".into()))
+        );
+        assert_eq!(graph.node_property(2, "name"), None);
+        let _ = graph.insert_node(Node::new(5));
+        assert_eq!(graph.remove_node_property(5, "name"), Ok(None));
+        assert_eq!(graph.get_node(5).unwrap().label, None);
+        assert_eq!(
+            graph.set_node_property(4, "name", "x"),
+            Err(GraphError::MissingNode { idx: 4 })
+        );
+
+        let _ = graph.set_edge_property(1, 2, "since", 1843.0);
+        assert_eq!(
+            graph.edge_property(2, 1, "since"),
+            Some(&PropertyValue::Number(1843.0))
+        );
+        let tags = graph
+            .edge_property(2, 1, "tags")
+            .and_then(PropertyValue::as_list);
+        assert_eq!(tags.map(<[_]>::len), Some(2));
+        assert_eq!(
+            graph.remove_edge_property(2, 1, "since"),
+            Ok(Some(1843.0.into()))
+        );
+        assert_eq!(graph.edge_property(1, 2, "since"), None);
+        assert_eq!(
+            graph.set_edge_property(1, 3, "since", 0.0),
+            Err(GraphError::MissingEdge { from: 1, to: 3 })
+        );
+
+        let named: Vec<u32> = graph
+            .nodes_where(|props| props.contains("name"))
+            .map(|node| node.idx)
+            .collect();
+        assert_eq!(named, vec![1]);
+        assert_eq!(
+            graph
+                .nodes_where(|props| props.get("active") == Some(&true.into()))
+                .count(),
+            1
+        );
+        assert_eq!(
+            graph
+                .edges_where(|props| props.label.as_deref() == Some("KNOWS"))
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_schema_validation() {
+        use property::{Properties, PropertyGraph};
+        use schema::{PropertyType, Schema};
+
+        let schema = Schema::new()
+            .node_property("Person", "name", PropertyType::String)
+            .edge("WORKS_AT", "Person", "Company")
+            .edge_property("WORKS_AT", "since", PropertyType::Number);
+        let mut graph: PropertyGraph = PropertyGraph::new(5, true);
+        let _ = graph.insert_node(Node::with_label(1, Properties::labelled("Person")));
+        assert_eq!(
+            graph.set_schema(schema.clone()),
+            Err(GraphError::InvalidNodeProperty {
+                idx: 1,
+                key: String::from("name"),
+                expected: PropertyType::String
+            })
+        );
+        assert!(graph.schema().is_none());
+        let _ = graph.set_node_property(1, "name", "Ada");
+        assert_eq!(graph.set_schema(schema), Ok(()));
+
+        let _ = graph.insert_node(Node::with_label(2, Properties::labelled("Company")));
+        let bad_name = Properties::labelled("Person").with("name", 1.0);
+        assert!(matches!(
+            graph.insert_node(Node::with_label(3, bad_name)),
+            Err(GraphError::InvalidNodeProperty { idx: 3, .. })
+        ));
+        assert!(matches!(
+            graph.remove_node_property(1, "name"),
+            Err(GraphError::InvalidNodeProperty { idx: 1, .. })
+        ));
+        assert!(graph.node_property(1, "name").is_some());
+
+        let works_at = Properties::labelled("WORKS_AT");
+        let alan = Properties::labelled("Person").with("name", "Alan");
+        let _ = graph.insert_node(Node::with_label(4, alan));
+        assert!(matches!(
+            graph.insert_edge_with_data(1, 4, 1.0, works_at.clone().with("since", 2020.0)),
+            Err(GraphError::InvalidEdgeEndpoints { from: 1, to: 4, .. })
+        ));
+        assert_eq!(
+            graph.insert_edge_with_data(1, 2, 1.0, works_at.clone()),
+            Err(GraphError::InvalidEdgeProperty {
+                from: 1,
+                to: 2,
+                key: String::from("since"),
+                expected: PropertyType::Number
+            })
+        );
+        let _ = graph.insert_edge_with_data(1, 2, 1.0, works_at.with("since", 2020.0));
+        assert!(graph.remove_edge_property(1, 2, "since").is_err());
+        assert_eq!(
+            graph.edges_where(|props| props.contains("since")).count(),
+            1
+        );
+        assert!(graph
+            .insert_edge_with_data(2, 2, 1.0, Properties::new())
+            .is_ok());
+
+        assert!(graph.remove_schema().is_some());
+        assert!(graph.remove_node_property(1, "name").is_ok());
+    }
+
+    #[test]
+    fn test_schema_edge_direction() {
+        use property::{Properties, PropertyGraph, PropertyValue};
+        use schema::Schema;
+
+        let schema = Schema::new().edge("WORKS_AT", "Person", "Company");
+        let works_at = || Properties::labelled("WORKS_AT");
+        let build = |undirected| {
+            let mut graph: PropertyGraph = PropertyGraph::new(5, undirected);
+            let _ = graph.insert_node(Node::with_label(1, Properties::labelled("Person")));
+            let _ = graph.insert_node(Node::with_label(2, Properties::labelled("Company")));
+            let _ = graph.set_schema(schema.clone());
+            let _ = graph.insert_edge_with_data(1, 2, 1.0, works_at());
+            graph
+        };
+
+        // An undirected edge can be updated and re-inserted from either end.
+        let mut undirected = build(true);
+        assert_eq!(
+            undirected.set_edge_property(2, 1, "since", 2020.0),
+            Ok(None)
+        );
+        assert_eq!(
+            undirected.edge_property(1, 2, "since"),
+            Some(&PropertyValue::Number(2020.0))
+        );
+        assert!(undirected
+            .insert_edge_with_data(2, 1, 2.0, works_at())
+            .is_ok());
+
+        let mut directed = build(false);
+        assert!(matches!(
+            directed.insert_edge_with_data(2, 1, 1.0, works_at()),
+            Err(GraphError::InvalidEdgeEndpoints { from: 2, to: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn test_breadth_first_search() {
+        use traversal::Bfs;
+
+        let mut graph: Graph<()> = Graph::new(10, true);
+        let _ = graph.insert_edges([
+            (1, 2, 1.0),
+            (2, 3, 1.0),
+            (3, 4, 1.0),
+            (5, 4, 1.0),
+            (6, 6, 1.0),
+        ]);
+
+        let steps: Vec<(u32, usize, Option<u32>)> = graph
+            .bfs(3)
+            .map(|step| (*step.idx, step.depth, step.parent.copied()))
+            .collect();
+        assert_eq!(steps.len(), 5);
+        assert_eq!(steps[0], (3, 0, None));
+        assert!(steps.contains(&(1, 2, Some(2))));
+        assert!(steps.contains(&(5, 2, Some(4))));
+
+        let steps: Vec<(u32, usize)> = Bfs::new(&graph, [1, 5, 99])
+            .map(|step| (*step.idx, step.depth))
+            .collect();
+        assert_eq!(steps.len(), 5);
+        assert!(steps.contains(&(3, 2)));
+        assert!(steps.contains(&(2, 1)) && steps.contains(&(4, 1)));
+
+        assert_eq!(graph.bfs(1).with_max_depth(1).count(), 2);
+        assert_eq!(graph.bfs(6).count(), 1);
+        assert_eq!(graph.bfs(1).take_while(|step| *step.idx != 3).count(), 2);
+
+        let directed: Graph<()> = Graph::from_edges([(1, 2, 1.0), (3, 2, 1.0)]).unwrap();
+        assert_eq!(directed.bfs(2).count(), 1);
+    }
+
+    #[test]
+    fn test_depth_first_search() {
+        use traversal::{DfsEvent, EdgeKind};
+
+        let kinds = |graph: &Graph<()>| -> Vec<(u32, u32, EdgeKind)> {
+            graph
+                .dfs_forest()
+                .filter_map(|event| match event {
+                    DfsEvent::Edge { edge, kind } => Some((edge.from_node, edge.to_node, kind)),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        // 1 -> 2 -> 3 -> 1 with a shortcut 1 -> 3, and 4 -> 2 in a second tree.
+        let graph: Graph<()> = Graph::from_edges([
+            (1, 2, 1.0),
+            (2, 3, 1.0),
+            (3, 1, 1.0),
+            (1, 3, 1.0),
+            (4, 2, 1.0),
+        ])
+        .unwrap();
+        let found = kinds(&graph);
+        assert_eq!(found.len(), 5);
+        assert!(found.contains(&(3, 1, EdgeKind::Back)));
+        assert!(found.contains(&(4, 2, EdgeKind::Cross)));
+        if found.contains(&(1, 3, EdgeKind::Tree)) {
+            assert!(found.contains(&(2, 3, EdgeKind::Cross)));
+        } else {
+            assert!(found.contains(&(1, 3, EdgeKind::Forward)));
+        }
+        assert_eq!(graph.dfs_forest().preorder().count(), 4);
+        assert_eq!(graph.dfs(4).postorder().last(), Some(&4));
+        assert_eq!(graph.dfs(9).count(), 0);
+
+        let mut dfs = graph.dfs_forest();
+        dfs.by_ref().for_each(drop);
+        for idx in 1..=4 {
+            let (discovered, finished) = (dfs.discovery_time(&idx), dfs.finish_time(&idx));
+            assert!(discovered.unwrap() < finished.unwrap());
+        }
+        assert_eq!(dfs.finish_time(&4), Some(7));
+
+        let undirected: Graph<()> = {
+            let mut graph = Graph::new(5, true);
+            let _ = graph.insert_edges([(1, 2, 1.0), (2, 3, 1.0), (3, 1, 1.0), (4, 4, 1.0)]);
+            graph
+        };
+        let found = kinds(&undirected);
+        assert_eq!(found.len(), 4);
+        assert_eq!(
+            found
+                .iter()
+                .filter(|(.., kind)| *kind == EdgeKind::Tree)
+                .count(),
+            2
+        );
+        assert!(found.contains(&(4, 4, EdgeKind::Back)));
+
+        let deep: Graph<()> = Graph::from_edges((0..100_000).map(|i| (i, i + 1, 1.0))).unwrap();
+        assert_eq!(deep.dfs(0).postorder().next(), Some(&100_000));
+    }
+
+    #[test]
+    fn test_inserting_node_with_dangling_edge() {
+        let mut graph: Graph<()> = Graph::new(5, false);
+        let _ = graph.insert_node(Node::new(1));
+        let mut node = Node::new(2);
+        node.add_edge(1, 1.0);
+        node.add_edge(99, 1.0);
+        assert_eq!(
+            graph.insert_node(node),
+            Err(GraphError::MissingTargetNode { idx: 99 })
+        );
+        assert!(!graph.has_node(2));
+        assert_eq!(graph.in_degree(1), Some(0));
+        assert!(graph.validate().is_valid());
+    }
+
+    #[test]
+    fn test_validating_weights_of_attached_edges() {
+        let mut graph: Graph<()> = Graph::new(5, false).with_weight_validator(|w| *w > 0.0);
+        let _ = graph.insert_node(Node::new(1));
+        for weight in [f32::NAN, -1.0] {
+            let mut node = Node::new(2);
+            node.add_edge(1, weight);
+            assert_eq!(
+                graph.insert_node(node),
+                Err(GraphError::InvalidWeight { from: 2, to: 1 })
+            );
+        }
+        assert!(!graph.has_node(2));
+        let mut node = Node::new(2);
+        node.add_edge(1, 2.0);
+        assert_eq!(graph.insert_node(node), Ok(()));
+    }
+
+    #[test]
+    fn test_mirroring_edges_of_inserted_node() {
+        let mut graph: Graph<()> = Graph::new(5, true);
+        let _ = graph.insert_node(Node::new(1));
+        let mut node = Node::new(2);
+        node.add_edge(1, 4.0);
+        node.add_edge(2, 1.0);
+        assert_eq!(graph.insert_node(node), Ok(()));
+
+        let forward = graph.get_edge(2, 1).unwrap();
+        let backward = graph.get_edge(1, 2).unwrap();
+        assert_eq!(forward.id(), backward.id());
+        assert_eq!(*backward.weight(), 4.0);
+        assert_eq!(graph.get_node(2).unwrap().number_of_edges(), 2);
+        assert!(graph.validate().is_valid());
+    }
+
+    #[test]
+    fn test_merging_node_edges() {
+        let mut graph: Graph<()> = Graph::new(5, true).with_self_loops(SelfLoops::Reject);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let id = graph.add_edge(1, 2, 1.0).unwrap();
+
+        let mut node = Node::new(1);
+        node.add_edge(2, 9.0);
+        assert_eq!(graph.insert_or_merge_node(node, |old, _| old), Ok(()));
+        assert_eq!(*graph.get_edge(2, 1).unwrap().weight(), 9.0);
+        assert_eq!(graph.get_edge(2, 1).unwrap().id(), id);
+        assert!(graph.validate().is_valid());
+
+        let mut node = Node::new(1);
+        node.add_edge(1, 1.0);
+        assert_eq!(
+            graph.insert_or_merge_node(node, |old, _| old),
+            Err(GraphError::SelfLoop { idx: 1 })
+        );
+        let mut node = Node::new(1);
+        node.add_edge(3, 1.0);
+        assert_eq!(
+            graph.insert_or_merge_node(node, |old, _| old),
+            Err(GraphError::MissingTargetNode { idx: 3 })
+        );
+        let mut node = Node::new(2);
+        node.add_edge(1, f32::NAN);
+        assert_eq!(
+            graph.insert_or_merge_node(node, |old, _| old),
+            Err(GraphError::InvalidWeight { from: 2, to: 1 })
+        );
+        assert_eq!(*graph.get_edge(1, 2).unwrap().weight(), 9.0);
+    }
+
+    #[test]
+    fn test_schema_checks_labels_and_attached_edges() {
+        use property::{Properties, PropertyGraph};
+        use schema::{PropertyType, Schema};
+
+        let schema = Schema::new()
+            .node_property("Person", "name", PropertyType::String)
+            .edge("WORKS_AT", "Person", "Company");
+        let mut graph: PropertyGraph = PropertyGraph::new(5, false);
+        graph.set_schema(schema).unwrap();
+        let ada = Properties::labelled("Person").with("name", "Ada");
+        let _ = graph.insert_node(Node::with_label(1, ada.clone()));
+        let _ = graph.insert_node(Node::with_label(2, Properties::labelled("Company")));
+        let _ = graph.insert_edge_with_data(1, 2, 1.0, Properties::labelled("WORKS_AT"));
+
+        // Relabelling through set_label.
+        assert!(matches!(
+            graph.set_label(2, Properties::labelled("Person")),
+            Err(GraphError::InvalidNodeProperty { idx: 2, .. })
+        ));
+        assert!(matches!(
+            graph.set_label(2, Properties::labelled("Person").with("name", "Bob")),
+            Err(GraphError::InvalidEdgeEndpoints { from: 1, to: 2, .. })
+        ));
+        assert_eq!(
+            graph.get_node(2).unwrap().label,
+            Some(Properties::labelled("Company"))
+        );
+
+        // Merged labels.
+        let person = |_, _| Some(Properties::labelled("Person"));
+        assert!(matches!(
+            graph.insert_or_merge_node(Node::new(1), person),
+            Err(GraphError::InvalidNodeProperty { idx: 1, .. })
+        ));
+        assert_eq!(graph.get_node(1).unwrap().label, Some(ada.clone()));
+
+        // Edges attached to inserted and merged nodes.
+        let _ = graph.insert_node(Node::with_label(3, Properties::labelled("Company")));
+        let mut node = Node::with_label(4, Properties::labelled("Company"));
+        node.add_edge_with_data(3, 1.0, Properties::labelled("WORKS_AT"));
+        assert!(matches!(
+            graph.insert_node(node),
+            Err(GraphError::InvalidEdgeEndpoints { from: 4, to: 3, .. })
+        ));
+        assert!(!graph.has_node(4));
+        let mut node = Node::new(3);
+        node.add_edge_with_data(2, 1.0, Properties::labelled("WORKS_AT"));
+        assert!(matches!(
+            graph.insert_or_merge_node(node, |old, _| old),
+            Err(GraphError::InvalidEdgeEndpoints { from: 3, to: 2, .. })
+        ));
+        assert!(!graph.is_edge(3, 2));
+    }
+
+    #[test]
+    fn test_removing_parallel_edge_from_node() {
+        let mut graph: Graph<()> = Graph::new_multigraph(5, false);
+        let _ = graph.insert_node(Node::new(1));
+        let _ = graph.insert_node(Node::new(2));
+        let first = graph.add_edge(1, 2, 1.0).unwrap();
+        let second = graph.add_edge(1, 2, 2.0).unwrap();
+
+        let mut node = graph.remove_node(1).unwrap();
+        let (neighbor, removed) = node.remove_edge(2).unwrap();
+        assert_eq!((neighbor, removed.id()), (2, first));
+        assert_eq!(node.number_of_edges(), 1);
+        assert_eq!(node.get_edge(2).unwrap().id(), second);
+        assert!(node.remove_edge(2).is_some());
+        assert!(node.remove_edge(2).is_none());
+        assert!(node.edges.is_empty());
+    }
 }
@@ -0,0 +1,64 @@
+//! Hooks for watching changes made to a [`Graph`].
+use crate::{Edge, Graph, Node};
+
+/// Receives a call for every node and edge mutation made through a [`Graph`],
+/// for logging, metrics or replication. Register one with
+/// [`Graph::add_observer`].
+/// Every method does nothing by default, so implementors only override the
+/// events they care about. Changes made through [`Graph::get_node_mut`],
+/// [`Graph::get_edge_mut`] or the public fields of the graph aren't reported.
+///
+/// # Example
+/// ```
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+/// use std::sync::Arc;
+/// use graphs::observer::GraphObserver;
+/// use graphs::{Edge, Graph, Node};
+///
+/// struct EdgeCounter(Arc<AtomicUsize>);
+///
+/// impl GraphObserver<()> for EdgeCounter {
+///     fn on_edge_inserted(&mut self, _edge: &Edge) {
+///         self.0.fetch_add(1, Ordering::Relaxed);
+///     }
+/// }
+///
+/// let count = Arc::new(AtomicUsize::new(0));
+/// let mut graph: Graph<()> = Graph::new(5, false);
+/// graph.add_observer(EdgeCounter(Arc::clone(&count)));
+/// graph.insert_node(Node::new(1)).unwrap();
+/// graph.insert_node(Node::new(2)).unwrap();
+/// graph.insert_edge(1, 2, 1.0).unwrap();
+/// assert_eq!(count.load(Ordering::Relaxed), 1);
+/// ```
+pub trait GraphObserver<T, W = f32, E = (), Id = u32> {
+    /// Called after a node has been inserted.
+    fn on_node_inserted(&mut self, _node: &Node<T, W, E, Id>) {}
+    /// Called after an existing node has been merged with another one by
+    /// [`Graph::insert_or_merge_node`], relabelled by [`Graph::set_label`], or
+    /// its properties have changed in a
+    /// [`PropertyGraph`](crate::property::PropertyGraph).
+    fn on_node_updated(&mut self, _node: &Node<T, W, E, Id>) {}
+    /// Called after a node has been removed. The edges pointing at the node
+    /// are removed with it without separate `on_edge_removed` calls.
+    fn on_node_removed(&mut self, _node: &Node<T, W, E, Id>) {}
+    /// Called after an edge has been inserted. In an undirected graph this is
+    /// called once per edge, not for its mirror.
+    fn on_edge_inserted(&mut self, _edge: &Edge<W, E, Id>) {}
+    /// Called after an existing edge has been replaced, or its weight or
+    /// properties have changed.
+    fn on_edge_updated(&mut self, _edge: &Edge<W, E, Id>) {}
+    /// Called after an edge has been removed.
+    fn on_edge_removed(&mut self, _edge: &Edge<W, E, Id>) {}
+}
+
+impl<T, W, E, Id> Graph<T, W, E, Id> {
+    /// Registers an observer that is notified of every later mutation of the
+    /// graph. Observers are called in the order they were added.
+    pub fn add_observer(
+        &mut self,
+        observer: impl GraphObserver<T, W, E, Id> + Send + Sync + 'static,
+    ) {
+        self.observers.push(Box::new(observer));
+    }
+}
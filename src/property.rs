@@ -0,0 +1,298 @@
+//! A property-graph layer: typed key/value properties on nodes and edges.
+use std::collections::HashMap;
+
+use crate::errors::GraphError;
+use crate::weight::Weight;
+use crate::{Edge, Graph, Node, NodeId};
+
+/// The value of a property.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyValue {
+    String(String),
+    Number(f64),
+    Bool(bool),
+    List(Vec<PropertyValue>),
+}
+
+impl PropertyValue {
+    /// Returns the string if the value is one.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            PropertyValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+    /// Returns the number if the value is one.
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            PropertyValue::Number(value) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Returns the boolean if the value is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            PropertyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+    /// Returns the list items if the value is a list.
+    pub fn as_list(&self) -> Option<&[PropertyValue]> {
+        match self {
+            PropertyValue::List(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl From<&str> for PropertyValue {
+    fn from(value: &str) -> Self {
+        PropertyValue::String(value.to_string())
+    }
+}
+
+impl From<String> for PropertyValue {
+    fn from(value: String) -> Self {
+        PropertyValue::String(value)
+    }
+}
+
+impl From<f64> for PropertyValue {
+    fn from(value: f64) -> Self {
+        PropertyValue::Number(value)
+    }
+}
+
+impl From<bool> for PropertyValue {
+    fn from(value: bool) -> Self {
+        PropertyValue::Bool(value)
+    }
+}
+
+impl<V: Into<PropertyValue>> From<Vec<V>> for PropertyValue {
+    fn from(values: Vec<V>) -> Self {
+        PropertyValue::List(values.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A set of named properties with an optional label, such as `Person` for a
+/// node or `WORKS_AT` for an edge.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Properties {
+    pub label: Option<String>,
+    values: HashMap<String, PropertyValue>,
+}
+
+impl Properties {
+    /// Creates an empty, unlabelled set of properties.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates an empty set of properties with a label.
+    pub fn labelled(label: &str) -> Self {
+        Self {
+            label: Some(label.to_string()),
+            values: HashMap::new(),
+        }
+    }
+    /// Adds a property and returns the set, for building properties inline.
+    ///
+    /// # Example
+    /// ```
+    /// use graphs::property::Properties;
+    ///
+    /// let person = Properties::labelled("Person").with("name", "Ada").with("age", 36.0);
+    /// assert_eq!(person.get("name").and_then(|v| v.as_str()), Some("Ada"));
+    /// ```
+    pub fn with(mut self, key: &str, value: impl Into<PropertyValue>) -> Self {
+        self.set(key, value);
+        self
+    }
+    /// Returns the value of a property.
+    pub fn get(&self, key: &str) -> Option<&PropertyValue> {
+        self.values.get(key)
+    }
+    /// Sets a property and returns its old value.
+    pub fn set(&mut self, key: &str, value: impl Into<PropertyValue>) -> Option<PropertyValue> {
+        self.values.insert(key.to_string(), value.into())
+    }
+    /// Removes a property and returns its value.
+    pub fn remove(&mut self, key: &str) -> Option<PropertyValue> {
+        self.values.remove(key)
+    }
+    /// Returns `true` if the property is set.
+    pub fn contains(&self, key: &str) -> bool {
+        self.values.contains_key(key)
+    }
+    /// Returns an iterator over the properties as `(key, value)`.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &PropertyValue)> {
+        self.values.iter().map(|(key, value)| (key.as_str(), value))
+    }
+}
+
+/// A graph whose node labels and edge payloads are [`Properties`].
+///
+/// # Example
+/// ```
+/// use graphs::property::{Properties, PropertyGraph};
+/// use graphs::Node;
+///
+/// let mut graph: PropertyGraph = PropertyGraph::new(5, false);
+/// graph.insert_node(Node::with_label(1, Properties::labelled("Person"))).unwrap();
+/// graph.insert_node(Node::with_label(2, Properties::labelled("Company"))).unwrap();
+/// graph
+///     .insert_edge_with_data(1, 2, 1.0, Properties::labelled("WORKS_AT").with("since", 2019.0))
+///     .unwrap();
+///
+/// graph.set_node_property(1, "name", "Ada").unwrap();
+/// let people: Vec<_> = graph
+///     .nodes_where(|props| props.label.as_deref() == Some("Person"))
+///     .collect();
+/// assert_eq!(people.len(), 1);
+/// assert_eq!(graph.edge_property(1, 2, "since").and_then(|v| v.as_number()), Some(2019.0));
+/// ```
+pub type PropertyGraph<W = f32, Id = u32> = Graph<Properties, W, Properties, Id>;
+
+impl<W: Weight, Id: NodeId> Graph<Properties, W, Properties, Id> {
+    /// Returns the value of a property of a node.
+    pub fn node_property(&self, idx: Id, key: &str) -> Option<&PropertyValue> {
+        self.node(&idx)?.label.as_ref()?.get(key)
+    }
+    /// Sets a property of a node and returns its old value. A node without
+    /// properties gets an unlabelled set.
+    /// Returns `GraphError::MissingNode` if the node doesn't exist, or the
+    /// schema's error if the change would violate it.
+    pub fn set_node_property(
+        &mut self,
+        idx: Id,
+        key: &str,
+        value: impl Into<PropertyValue>,
+    ) -> Result<Option<PropertyValue>, GraphError<Id>> {
+        self.update_node_properties(idx, |props| props.set(key, value))
+    }
+    /// Removes a property of a node and returns its value. A node without
+    /// properties is left unchanged.
+    pub fn remove_node_property(
+        &mut self,
+        idx: Id,
+        key: &str,
+    ) -> Result<Option<PropertyValue>, GraphError<Id>> {
+        if self.node(&idx).is_some_and(|node| node.label.is_none()) {
+            return Ok(None);
+        }
+        self.update_node_properties(idx, |props| props.remove(key))
+    }
+    /// Returns the value of a property of the edge from `from` to `to`. If
+    /// there are parallel edges, the oldest one is used.
+    pub fn edge_property(&self, from: Id, to: Id, key: &str) -> Option<&PropertyValue> {
+        self.get_edge(from, to)?.data().get(key)
+    }
+    /// Sets a property of the edge from `from` to `to` and returns its old
+    /// value. In an undirected graph the mirrored edge is updated as well.
+    /// Fails if either node or the edge is missing, or if the change would
+    /// violate the graph's schema.
+    pub fn set_edge_property(
+        &mut self,
+        from: Id,
+        to: Id,
+        key: &str,
+        value: impl Into<PropertyValue>,
+    ) -> Result<Option<PropertyValue>, GraphError<Id>> {
+        self.update_edge_properties(from, to, |props| props.set(key, value))
+    }
+    /// Removes a property of the edge from `from` to `to` and returns its
+    /// value. In an undirected graph the mirrored edge is updated as well.
+    pub fn remove_edge_property(
+        &mut self,
+        from: Id,
+        to: Id,
+        key: &str,
+    ) -> Result<Option<PropertyValue>, GraphError<Id>> {
+        self.update_edge_properties(from, to, |props| props.remove(key))
+    }
+    /// Returns an iterator over the nodes whose properties match `predicate`.
+    /// Nodes without properties are skipped.
+    pub fn nodes_where<F>(
+        &self,
+        predicate: F,
+    ) -> impl Iterator<Item = &Node<Properties, W, Properties, Id>>
+    where
+        F: Fn(&Properties) -> bool,
+    {
+        self.nodes
+            .iter()
+            .filter(move |node| node.label.as_ref().is_some_and(&predicate))
+    }
+    /// Returns an iterator over the edges whose properties match `predicate`.
+    /// In an undirected graph each edge is yielded once.
+    pub fn edges_where<F>(&self, predicate: F) -> impl Iterator<Item = &Edge<W, Properties, Id>>
+    where
+        F: Fn(&Properties) -> bool,
+    {
+        self.edge_refs().filter(move |edge| predicate(edge.data()))
+    }
+    fn update_node_properties<R>(
+        &mut self,
+        idx: Id,
+        update: impl FnOnce(&mut Properties) -> R,
+    ) -> Result<R, GraphError<Id>> {
+        let Some(&pos) = self.index.get(&idx) else {
+            return Err(GraphError::MissingNode { idx });
+        };
+        let label = &mut self.nodes[pos].label;
+        let result = match &self.schema {
+            None => update(label.get_or_insert_with(Properties::new)),
+            Some(schema) => {
+                let mut props = label.clone().unwrap_or_default();
+                let result = update(&mut props);
+                let old = label.replace(props);
+                if let Err(err) = schema.check_node(&self.nodes[pos]) {
+                    self.nodes[pos].label = old;
+                    return Err(err);
+                }
+                result
+            }
+        };
+        for observer in self.observers.iter_mut() {
+            observer.on_node_updated(&self.nodes[pos]);
+        }
+        Ok(result)
+    }
+    fn update_edge_properties<R>(
+        &mut self,
+        from: Id,
+        to: Id,
+        update: impl FnOnce(&mut Properties) -> R,
+    ) -> Result<R, GraphError<Id>> {
+        if !self.index.contains_key(&from) {
+            return Err(GraphError::MissingSourceNode { idx: from });
+        }
+        if !self.index.contains_key(&to) {
+            return Err(GraphError::MissingTargetNode { idx: to });
+        }
+        let Some(edge) = self.get_edge(from.clone(), to.clone()) else {
+            return Err(GraphError::MissingEdge { from, to });
+        };
+        let id = edge.id();
+        let mut props = edge.data().clone();
+        let result = update(&mut props);
+        if let Some(schema) = &self.schema {
+            self.check_schema_edge(
+                schema,
+                &self.nodes[self.index[&from]],
+                &self.nodes[self.index[&to]],
+                &props,
+            )?;
+        }
+        if let Some(mut edge) = self.edge_mut(&from, &to, id) {
+            *edge.data_mut() = props;
+        }
+        let src_node_idx = self.index[&from];
+        if let Some(edge) = self.nodes[src_node_idx].edge_mut(&to, id) {
+            for observer in self.observers.iter_mut() {
+                observer.on_edge_updated(edge);
+            }
+        }
+        Ok(result)
+    }
+}
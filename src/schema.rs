@@ -0,0 +1,312 @@
+//! Schemas that constrain the labels and properties of a
+//! [`PropertyGraph`](crate::property::PropertyGraph).
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::errors::GraphError;
+use crate::property::{Properties, PropertyValue};
+use crate::weight::Weight;
+use crate::{Edge, Graph, Node, NodeId};
+
+/// The type of a [`PropertyValue`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PropertyType {
+    String,
+    Number,
+    Bool,
+    List,
+}
+
+impl PropertyType {
+    /// Returns the type of a value.
+    pub fn of(value: &PropertyValue) -> Self {
+        match value {
+            PropertyValue::String(_) => PropertyType::String,
+            PropertyValue::Number(_) => PropertyType::Number,
+            PropertyValue::Bool(_) => PropertyType::Bool,
+            PropertyValue::List(_) => PropertyType::List,
+        }
+    }
+}
+
+impl fmt::Display for PropertyType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PropertyType::String => "string",
+            PropertyType::Number => "number",
+            PropertyType::Bool => "bool",
+            PropertyType::List => "list",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Rules for the nodes and edges of a property graph, keyed by label.
+/// Nodes and edges whose label has no rules, or that have no label, are
+/// accepted as they are.
+///
+/// # Example
+/// ```
+/// use graphs::errors::GraphError;
+/// use graphs::property::{Properties, PropertyGraph};
+/// use graphs::schema::{PropertyType, Schema};
+/// use graphs::Node;
+///
+/// let schema = Schema::new()
+///     .node_property("Person", "name", PropertyType::String)
+///     .edge("WORKS_AT", "Person", "Company");
+/// let mut graph: PropertyGraph = PropertyGraph::new(5, false);
+/// graph.set_schema(schema).unwrap();
+///
+/// let ada = Properties::labelled("Person").with("name", "Ada");
+/// graph.insert_node(Node::with_label(1, ada)).unwrap();
+/// graph.insert_node(Node::with_label(2, Properties::labelled("Person"))).unwrap_err();
+/// graph.insert_node(Node::with_label(3, Properties::labelled("Company"))).unwrap();
+///
+/// let works_at = Properties::labelled("WORKS_AT");
+/// assert!(graph.insert_edge_with_data(1, 3, 1.0, works_at.clone()).is_ok());
+/// assert!(matches!(
+///     graph.insert_edge_with_data(3, 1, 1.0, works_at),
+///     Err(GraphError::InvalidEdgeEndpoints { from: 3, to: 1, .. })
+/// ));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    node_properties: HashMap<String, Vec<(String, PropertyType)>>,
+    edge_properties: HashMap<String, Vec<(String, PropertyType)>>,
+    edge_endpoints: HashMap<String, Vec<(String, String)>>,
+}
+
+impl Schema {
+    /// Creates a schema without rules.
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Requires nodes labelled `label` to have a property `key` of type
+    /// `expected`.
+    pub fn node_property(mut self, label: &str, key: &str, expected: PropertyType) -> Self {
+        self.node_properties
+            .entry(label.to_string())
+            .or_default()
+            .push((key.to_string(), expected));
+        self
+    }
+    /// Requires edges labelled `label` to have a property `key` of type
+    /// `expected`.
+    pub fn edge_property(mut self, label: &str, key: &str, expected: PropertyType) -> Self {
+        self.edge_properties
+            .entry(label.to_string())
+            .or_default()
+            .push((key.to_string(), expected));
+        self
+    }
+    /// Allows edges labelled `label` to go from nodes labelled `from` to
+    /// nodes labelled `to`. Once a label has an allowed pair, edges with
+    /// that label between any other labels are rejected. In an undirected
+    /// graph, an edge may connect the two labels either way round.
+    pub fn edge(mut self, label: &str, from: &str, to: &str) -> Self {
+        self.edge_endpoints
+            .entry(label.to_string())
+            .or_default()
+            .push((from.to_string(), to.to_string()));
+        self
+    }
+    /// Returns the first required property that `props` lacks or has with the
+    /// wrong type.
+    fn missing<'a>(
+        rules: &'a HashMap<String, Vec<(String, PropertyType)>>,
+        props: &Properties,
+    ) -> Option<&'a (String, PropertyType)> {
+        let rules = rules.get(props.label.as_deref()?)?;
+        rules.iter().find(|(key, expected)| {
+            props
+                .get(key)
+                .is_none_or(|value| PropertyType::of(value) != *expected)
+        })
+    }
+    /// Checks a node against the schema.
+    pub fn check_node<W, Id: NodeId>(
+        &self,
+        node: &Node<Properties, W, Properties, Id>,
+    ) -> Result<(), GraphError<Id>> {
+        let Some(props) = &node.label else {
+            return Ok(());
+        };
+        match Self::missing(&self.node_properties, props) {
+            Some((key, expected)) => Err(GraphError::InvalidNodeProperty {
+                idx: node.idx.clone(),
+                key: key.clone(),
+                expected: *expected,
+            }),
+            None => Ok(()),
+        }
+    }
+    /// Checks an edge carrying `props` from `from` to `to` against the
+    /// schema.
+    pub fn check_edge<W, Id: NodeId>(
+        &self,
+        from: &Node<Properties, W, Properties, Id>,
+        to: &Node<Properties, W, Properties, Id>,
+        props: &Properties,
+    ) -> Result<(), GraphError<Id>> {
+        let Some(label) = &props.label else {
+            return Ok(());
+        };
+        if let Some(pairs) = self.edge_endpoints.get(label) {
+            let label_of = |node: &Node<Properties, W, Properties, Id>| {
+                node.label.as_ref().and_then(|props| props.label.clone())
+            };
+            let (from_label, to_label) = (label_of(from), label_of(to));
+            let allowed = pairs
+                .iter()
+                .any(|(a, b)| from_label.as_ref() == Some(a) && to_label.as_ref() == Some(b));
+            if !allowed {
+                return Err(GraphError::InvalidEdgeEndpoints {
+                    from: from.idx.clone(),
+                    to: to.idx.clone(),
+                    label: label.clone(),
+                });
+            }
+        }
+        match Self::missing(&self.edge_properties, props) {
+            Some((key, expected)) => Err(GraphError::InvalidEdgeProperty {
+                from: from.idx.clone(),
+                to: to.idx.clone(),
+                key: key.clone(),
+                expected: *expected,
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A schema attached to a graph, along with the functions that check the
+/// graph's nodes and edges against it.
+/// The functions are stored when the schema is attached, so the generic
+/// insertion methods of [`Graph`] can call them.
+pub(crate) struct SchemaCheck<T, W, E, Id> {
+    pub(crate) schema: Schema,
+    node: NodeCheck<T, W, E, Id>,
+    edge: EdgeCheck<T, W, E, Id>,
+    clone_label: fn(&T) -> T,
+}
+
+type NodeCheck<T, W, E, Id> = fn(&Schema, &Node<T, W, E, Id>) -> Result<(), GraphError<Id>>;
+type EdgeCheck<T, W, E, Id> =
+    fn(&Schema, &Node<T, W, E, Id>, &Node<T, W, E, Id>, &E) -> Result<(), GraphError<Id>>;
+
+impl<T, W, E, Id> SchemaCheck<T, W, E, Id> {
+    pub(crate) fn check_node(&self, node: &Node<T, W, E, Id>) -> Result<(), GraphError<Id>> {
+        (self.node)(&self.schema, node)
+    }
+    pub(crate) fn check_edge(
+        &self,
+        from: &Node<T, W, E, Id>,
+        to: &Node<T, W, E, Id>,
+        data: &E,
+    ) -> Result<(), GraphError<Id>> {
+        (self.edge)(&self.schema, from, to, data)
+    }
+    /// Copies a label, so a change can be undone if it violates the schema.
+    pub(crate) fn clone_label(&self, label: &T) -> T {
+        (self.clone_label)(label)
+    }
+}
+
+impl<T, W: Weight, E, Id: NodeId> Graph<T, W, E, Id> {
+    /// Returns the schema attached to the graph, if any.
+    pub fn schema(&self) -> Option<&Schema> {
+        self.schema.as_ref().map(|check| &check.schema)
+    }
+    /// Detaches the schema from the graph and returns it.
+    pub fn remove_schema(&mut self) -> Option<Schema> {
+        self.schema.take().map(|check| check.schema)
+    }
+    /// Checks `node` against the attached schema, along with its edges and
+    /// the edges pointing at it in the graph, and the `attached` edges that
+    /// are about to be added to it. `node` may be in the graph or about to
+    /// join it. Edges are checked with [`Graph::check_schema_edge`].
+    pub(crate) fn check_schema(
+        &self,
+        node: &Node<T, W, E, Id>,
+        attached: &HashMap<Id, Vec<Edge<W, E, Id>>>,
+    ) -> Result<(), GraphError<Id>> {
+        let Some(schema) = &self.schema else {
+            return Ok(());
+        };
+        schema.check_node(node)?;
+        let endpoint = |idx: &Id| match *idx == node.idx {
+            true => Some(node),
+            false => self.node(idx),
+        };
+        for (to, edges) in attached {
+            if let Some(to) = endpoint(to) {
+                for edge in edges {
+                    self.check_schema_edge(schema, node, to, &edge.data)?;
+                }
+            }
+        }
+        for (to, edges) in &node.edges {
+            if let Some(to) = endpoint(to) {
+                for edge in edges {
+                    self.check_schema_edge(schema, node, to, &edge.data)?;
+                }
+            }
+        }
+        for pred in self.predecessors(node.idx.clone()) {
+            let Some(from) = self.node(pred).filter(|from| from.idx != node.idx) else {
+                continue;
+            };
+            for edge in from.edges.get(&node.idx).into_iter().flatten() {
+                self.check_schema_edge(schema, from, node, &edge.data)?;
+            }
+        }
+        Ok(())
+    }
+    /// Checks an edge from `from` to `to` against the schema. An edge of an
+    /// undirected graph is stored in both directions, so either one may
+    /// satisfy the schema.
+    pub(crate) fn check_schema_edge(
+        &self,
+        schema: &SchemaCheck<T, W, E, Id>,
+        from: &Node<T, W, E, Id>,
+        to: &Node<T, W, E, Id>,
+        data: &E,
+    ) -> Result<(), GraphError<Id>> {
+        match schema.check_edge(from, to, data) {
+            Err(err) if self.undirected => schema.check_edge(to, from, data).map_err(|_| err),
+            result => result,
+        }
+    }
+}
+
+impl<W: Weight, Id: NodeId> Graph<Properties, W, Properties, Id> {
+    /// Attaches a schema to the graph, replacing any previous one.
+    /// From then on [`Graph::insert_node`], [`Graph::insert_edge`] and
+    /// friends, and the property setters reject nodes and edges that violate
+    /// it, and so do [`Graph::set_label`] and [`Graph::insert_or_merge_node`]
+    /// when the new label doesn't fit the node or its edges.
+    /// The nodes and edges already in the graph are checked first; if one
+    /// violates the schema, its error is returned and the graph is left
+    /// unchanged. An edge of an undirected graph may satisfy the schema in
+    /// either direction.
+    pub fn set_schema(&mut self, schema: Schema) -> Result<(), GraphError<Id>> {
+        let check = SchemaCheck {
+            schema,
+            node: Schema::check_node,
+            edge: Schema::check_edge,
+            clone_label: Properties::clone,
+        };
+        for node in &self.nodes {
+            check.check_node(node)?;
+        }
+        for edge in self.edge_refs() {
+            if let (Some(from), Some(to)) = (self.node(edge.from_node()), self.node(edge.to_node()))
+            {
+                self.check_schema_edge(&check, from, to, edge.data())?;
+            }
+        }
+        self.schema = Some(check);
+        Ok(())
+    }
+}
@@ -0,0 +1,281 @@
+//! Graph traversals.
+use std::collections::{hash_map, HashMap, HashSet, VecDeque};
+use std::iter::Flatten;
+
+use crate::weight::Weight;
+use crate::{Edge, EdgeId, Graph, Node, NodeId};
+
+/// A node reached by a [`Bfs`], with its distance in edges from the nearest
+/// source and the node it was reached from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BfsNode<'a, Id> {
+    pub idx: &'a Id,
+    pub depth: usize,
+    /// The parent of the node in the BFS tree, `None` for the sources.
+    pub parent: Option<&'a Id>,
+}
+
+/// A breadth-first traversal of a [`Graph`], created by [`Graph::bfs`] or
+/// [`Bfs::new`].
+/// It follows outgoing edges, which in an undirected graph include the
+/// mirrored ones, so edges are followed both ways. Each node is visited once.
+/// The traversal is lazy: stop consuming the iterator to stop the search.
+///
+/// # Example
+/// ```
+/// use graphs::Graph;
+///
+/// let graph: Graph<()> = Graph::from_edges([(1, 2, 1.0), (2, 3, 1.0), (3, 4, 1.0)]).unwrap();
+/// let found = graph.bfs(1).find(|step| *step.idx == 3).unwrap();
+/// assert_eq!((found.depth, found.parent), (2, Some(&2)));
+/// ```
+pub struct Bfs<'a, T, W, E, Id> {
+    graph: &'a Graph<T, W, E, Id>,
+    queue: VecDeque<BfsNode<'a, Id>>,
+    visited: HashSet<&'a Id>,
+    max_depth: Option<usize>,
+}
+
+impl<'a, T, W: Weight, E, Id: NodeId> Bfs<'a, T, W, E, Id> {
+    /// Starts a traversal from every node in `sources`, all at depth 0.
+    /// Sources that aren't in the graph are skipped.
+    pub fn new(graph: &'a Graph<T, W, E, Id>, sources: impl IntoIterator<Item = Id>) -> Self {
+        let mut bfs = Self {
+            graph,
+            queue: VecDeque::new(),
+            visited: HashSet::new(),
+            max_depth: None,
+        };
+        for source in sources {
+            if let Some(node) = graph.node(&source) {
+                bfs.enqueue(&node.idx, 0, None);
+            }
+        }
+        bfs
+    }
+    /// Stops the traversal from going further than `depth` edges from the
+    /// sources.
+    pub fn with_max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = Some(depth);
+        self
+    }
+    fn enqueue(&mut self, idx: &'a Id, depth: usize, parent: Option<&'a Id>) {
+        if self.visited.insert(idx) {
+            self.queue.push_back(BfsNode { idx, depth, parent });
+        }
+    }
+}
+
+impl<'a, T, W: Weight, E, Id: NodeId> Iterator for Bfs<'a, T, W, E, Id> {
+    type Item = BfsNode<'a, Id>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let step = self.queue.pop_front()?;
+        if self.max_depth.is_none_or(|max| step.depth < max) {
+            let graph = self.graph;
+            for neighbor in graph.neighbors(step.idx.clone()) {
+                if let Some(node) = graph.node(neighbor) {
+                    self.enqueue(&node.idx, step.depth + 1, Some(step.idx));
+                }
+            }
+        }
+        Some(step)
+    }
+}
+
+/// The kind of an edge met by a [`Dfs`], relative to the DFS forest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// The edge led to a newly discovered node.
+    Tree,
+    /// The edge leads back to an ancestor of its source, or is a self-loop.
+    Back,
+    /// The edge leads to an already finished descendant of its source.
+    Forward,
+    /// The edge leads to a finished node in another branch or tree.
+    Cross,
+}
+
+/// A step of a [`Dfs`]. Discovery and finish times share one clock that
+/// ticks at every discovery and every finish, starting at 0.
+#[derive(Debug, PartialEq)]
+pub enum DfsEvent<'a, W, E, Id> {
+    /// The node was reached for the first time; these come in preorder.
+    Discover { idx: &'a Id, time: usize },
+    /// An edge was examined from the node currently being explored.
+    Edge {
+        edge: &'a Edge<W, E, Id>,
+        kind: EdgeKind,
+    },
+    /// All edges of the node have been explored; these come in postorder.
+    Finish { idx: &'a Id, time: usize },
+}
+
+/// The edges of a node, flattened across its neighbors.
+type NodeEdges<'a, W, E, Id> = Flatten<hash_map::Values<'a, Id, Vec<Edge<W, E, Id>>>>;
+
+/// The node being explored by a [`Dfs`] and its edges that are left.
+struct Frame<'a, W, E, Id> {
+    idx: &'a Id,
+    edges: NodeEdges<'a, W, E, Id>,
+    /// The tree edge the node was discovered through.
+    via: Option<EdgeId>,
+}
+
+/// An iterative depth-first traversal of a [`Graph`], created by
+/// [`Graph::dfs`] or [`Graph::dfs_forest`]. It yields a [`DfsEvent`] for each
+/// discovered node, examined edge and finished node, and keeps its own stack,
+/// so deep graphs don't overflow the call stack.
+/// In an undirected graph every edge is reported once, as a tree or back
+/// edge.
+///
+/// # Example
+/// ```
+/// use graphs::traversal::{DfsEvent, EdgeKind};
+/// use graphs::Graph;
+///
+/// let graph: Graph<()> = Graph::from_edges([(1, 2, 1.0), (2, 3, 1.0), (3, 1, 1.0)]).unwrap();
+/// let preorder: Vec<_> = graph.dfs(1).preorder().collect();
+/// assert_eq!(preorder, vec![&1, &2, &3]);
+///
+/// let back_edges = graph
+///     .dfs(1)
+///     .filter(|event| matches!(event, DfsEvent::Edge { kind: EdgeKind::Back, .. }))
+///     .count();
+/// assert_eq!(back_edges, 1);
+/// ```
+pub struct Dfs<'a, T, W, E, Id> {
+    graph: &'a Graph<T, W, E, Id>,
+    roots: std::vec::IntoIter<&'a Node<T, W, E, Id>>,
+    stack: Vec<Frame<'a, W, E, Id>>,
+    discovered: HashMap<&'a Id, usize>,
+    finished: HashMap<&'a Id, usize>,
+    time: usize,
+    pending: Option<DfsEvent<'a, W, E, Id>>,
+}
+
+impl<'a, T, W: Weight, E, Id: NodeId> Dfs<'a, T, W, E, Id> {
+    fn with_roots(graph: &'a Graph<T, W, E, Id>, roots: Vec<&'a Node<T, W, E, Id>>) -> Self {
+        Self {
+            graph,
+            roots: roots.into_iter(),
+            stack: Vec::new(),
+            discovered: HashMap::new(),
+            finished: HashMap::new(),
+            time: 0,
+            pending: None,
+        }
+    }
+    /// Returns the discovery time of a node, if it has been discovered.
+    pub fn discovery_time(&self, idx: &Id) -> Option<usize> {
+        self.discovered.get(idx).copied()
+    }
+    /// Returns the finish time of a node, if it has been finished.
+    pub fn finish_time(&self, idx: &Id) -> Option<usize> {
+        self.finished.get(idx).copied()
+    }
+    /// Returns the nodes in the order they are discovered.
+    pub fn preorder(self) -> impl Iterator<Item = &'a Id> {
+        self.filter_map(|event| match event {
+            DfsEvent::Discover { idx, .. } => Some(idx),
+            _ => None,
+        })
+    }
+    /// Returns the nodes in the order they are finished.
+    pub fn postorder(self) -> impl Iterator<Item = &'a Id> {
+        self.filter_map(|event| match event {
+            DfsEvent::Finish { idx, .. } => Some(idx),
+            _ => None,
+        })
+    }
+    /// Pushes a newly discovered node and returns its discovery event.
+    fn discover(
+        &mut self,
+        node: &'a Node<T, W, E, Id>,
+        via: Option<EdgeId>,
+    ) -> DfsEvent<'a, W, E, Id> {
+        let idx = &node.idx;
+        let time = self.tick();
+        self.discovered.insert(idx, time);
+        let edges = node.edges.values().flatten();
+        self.stack.push(Frame { idx, edges, via });
+        DfsEvent::Discover { idx, time }
+    }
+    fn tick(&mut self) -> usize {
+        self.time += 1;
+        self.time - 1
+    }
+    /// Classifies an edge to a node that has already been discovered, or
+    /// returns `None` if an undirected graph has already reported it.
+    fn classify(&self, from: &Id, to: &Id) -> Option<EdgeKind> {
+        if !self.finished.contains_key(to) {
+            Some(EdgeKind::Back)
+        } else if self.graph.undirected {
+            None
+        } else if self.discovered[from] < self.discovered[to] {
+            Some(EdgeKind::Forward)
+        } else {
+            Some(EdgeKind::Cross)
+        }
+    }
+}
+
+impl<'a, T, W: Weight, E, Id: NodeId> Iterator for Dfs<'a, T, W, E, Id> {
+    type Item = DfsEvent<'a, W, E, Id>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+        loop {
+            let Some(frame) = self.stack.last_mut() else {
+                let root = self
+                    .roots
+                    .find(|node| !self.discovered.contains_key(&node.idx))?;
+                return Some(self.discover(root, None));
+            };
+            let from = frame.idx;
+            let Some(edge) = frame.edges.next() else {
+                self.stack.pop();
+                let time = self.tick();
+                self.finished.insert(from, time);
+                return Some(DfsEvent::Finish { idx: from, time });
+            };
+            if self.graph.undirected && frame.via == Some(edge.id()) {
+                continue;
+            }
+            let Some(to) = self.graph.node(edge.to_node()) else {
+                continue;
+            };
+            let kind = if self.discovered.contains_key(&to.idx) {
+                match self.classify(from, &to.idx) {
+                    Some(kind) => kind,
+                    None => continue,
+                }
+            } else {
+                self.pending = Some(self.discover(to, Some(edge.id())));
+                EdgeKind::Tree
+            };
+            return Some(DfsEvent::Edge { edge, kind });
+        }
+    }
+}
+
+impl<T, W: Weight, E, Id: NodeId> Graph<T, W, E, Id> {
+    /// Returns a breadth-first traversal starting at `source`; see [`Bfs`].
+    pub fn bfs(&self, source: Id) -> Bfs<'_, T, W, E, Id> {
+        Bfs::new(self, [source])
+    }
+    /// Returns a depth-first traversal of the nodes reachable from `source`;
+    /// see [`Dfs`]. It is empty if the node doesn't exist.
+    pub fn dfs(&self, source: Id) -> Dfs<'_, T, W, E, Id> {
+        let roots = self.node(&source).into_iter().collect();
+        Dfs::with_roots(self, roots)
+    }
+    /// Returns a depth-first traversal of every node in the graph. Each node
+    /// that hasn't been reached when its turn comes, in the order of `nodes`,
+    /// starts a new tree of the DFS forest.
+    pub fn dfs_forest(&self) -> Dfs<'_, T, W, E, Id> {
+        Dfs::with_roots(self, self.nodes.iter().collect())
+    }
+}
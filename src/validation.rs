@@ -0,0 +1,76 @@
+//! Structural checks over a whole [`Graph`].
+use std::cmp::Ordering;
+
+use crate::weight::Weight;
+use crate::{Graph, NodeId};
+
+/// A report of the integrity problems found in a graph by [`Graph::validate`].
+/// Each entry names the edge by the index numbers of its endpoints.
+#[derive(Debug, PartialEq)]
+pub struct ValidationReport<Id = u32> {
+    /// Edges, as `(from, to)`, whose target node is not in the graph.
+    pub dangling_edges: Vec<(Id, Id)>,
+    /// Edges, as `(owner, from_node)`, stored on a node whose index number
+    /// differs from the edge's `from_node`.
+    pub mismatched_edges: Vec<(Id, Id)>,
+    /// Edges, as `(from, to)`, in an undirected graph with no matching edge
+    /// (one with the same [`EdgeId`](crate::EdgeId)) from `to` back to `from`,
+    /// or whose mirror carries a different weight.
+    pub asymmetric_edges: Vec<(Id, Id)>,
+}
+
+impl<Id> Default for ValidationReport<Id> {
+    fn default() -> Self {
+        Self {
+            dangling_edges: Vec::new(),
+            mismatched_edges: Vec::new(),
+            asymmetric_edges: Vec::new(),
+        }
+    }
+}
+
+impl<Id> ValidationReport<Id> {
+    /// Returns `true` if no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.dangling_edges.is_empty()
+            && self.mismatched_edges.is_empty()
+            && self.asymmetric_edges.is_empty()
+    }
+}
+
+impl<T, W: Weight, E, Id: NodeId> Graph<T, W, E, Id> {
+    /// Checks every edge in the graph and reports dangling, mismatched and
+    /// (for undirected graphs) asymmetric edges.
+    /// The `Graph` methods never produce these, but editing `nodes` or
+    /// `Node::edges` directly can.
+    pub fn validate(&self) -> ValidationReport<Id> {
+        let mut report = ValidationReport::default();
+        for node in self.nodes.iter() {
+            for (to, edge) in node
+                .edges
+                .iter()
+                .flat_map(|(to, edges)| edges.iter().map(move |edge| (to, edge)))
+            {
+                if edge.from_node != node.idx {
+                    report
+                        .mismatched_edges
+                        .push((node.idx.clone(), edge.from_node.clone()));
+                }
+                if !self.has_node(to.clone()) {
+                    report.dangling_edges.push((node.idx.clone(), to.clone()));
+                    continue;
+                }
+                if self.undirected {
+                    let mirror = self.get_edge_by_id(to.clone(), node.idx.clone(), edge.id);
+                    let symmetric = mirror.is_some_and(|mirror| {
+                        mirror.weight.total_cmp(&edge.weight) == Ordering::Equal
+                    });
+                    if !symmetric {
+                        report.asymmetric_edges.push((node.idx.clone(), to.clone()));
+                    }
+                }
+            }
+        }
+        report
+    }
+}
@@ -0,0 +1,87 @@
+//! The [`Weight`] trait for edge weight types.
+use std::cmp::Ordering;
+use std::ops::Add;
+
+/// A type that can be used as the weight of an [`Edge`](crate::Edge).
+/// Weights can be summed, start from a zero value and have a total order, so
+/// path costs can be accumulated and compared.
+///
+/// It is implemented for the primitive integer and float types. Implement it
+/// for your own type to use, for example, exact decimal or rational weights.
+///
+/// # Example
+/// ```
+/// use std::cmp::Ordering;
+/// use std::ops::Add;
+/// use graphs::weight::Weight;
+///
+/// /// Money in cents.
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Cents(i64);
+///
+/// impl Add for Cents {
+///     type Output = Cents;
+///     fn add(self, other: Cents) -> Cents {
+///         Cents(self.0 + other.0)
+///     }
+/// }
+///
+/// impl Weight for Cents {
+///     fn zero() -> Self {
+///         Cents(0)
+///     }
+///     fn total_cmp(&self, other: &Self) -> Ordering {
+///         self.0.cmp(&other.0)
+///     }
+/// }
+///
+/// assert_eq!(Cents::zero() + Cents(250), Cents(250));
+/// ```
+pub trait Weight: Clone + Add<Output = Self> {
+    /// Returns the additive identity, the weight of an empty path.
+    fn zero() -> Self;
+    /// Compares two weights. Unlike `PartialOrd`, every pair of weights must
+    /// be ordered.
+    fn total_cmp(&self, other: &Self) -> Ordering;
+    /// Returns `false` if this weight can't be stored on an edge.
+    /// Float weights reject `NaN`; every other weight is valid by default.
+    fn is_valid(&self) -> bool {
+        true
+    }
+}
+
+macro_rules! impl_weight_for_int {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> Self {
+                    0
+                }
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    self.cmp(other)
+                }
+            }
+        )*
+    };
+}
+
+macro_rules! impl_weight_for_float {
+    ($($t:ty),*) => {
+        $(
+            impl Weight for $t {
+                fn zero() -> Self {
+                    0.0
+                }
+                fn total_cmp(&self, other: &Self) -> Ordering {
+                    <$t>::total_cmp(self, other)
+                }
+                fn is_valid(&self) -> bool {
+                    !self.is_nan()
+                }
+            }
+        )*
+    };
+}
+
+impl_weight_for_int!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+impl_weight_for_float!(f32, f64);